@@ -1,9 +1,14 @@
 use crate::{
     event::{Event as CalEvent, EventTime as CalEventTime},
-    files::Files,
+    files::Config,
+    notify::{notify, reminder_message},
     popup::{input::PopupInput, state::PopupState},
+    store::Store,
     ui,
-    widgets::{calendar::CalendarState, event_view::EventViewState},
+    widgets::{
+        calendar::CalendarState, event_view::EventViewState, search_view::SearchViewState,
+        todo_view::TodoViewState, trash_view::TrashViewState,
+    },
 };
 use anyhow::Result;
 use chrono::prelude::*;
@@ -13,16 +18,23 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use log2::{error, info};
 use std::{
     error::Error,
-    io,
-    time::{Duration, Instant},
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
 };
 use tui::{
     backend::{Backend, CrosstermBackend},
     Terminal,
 };
 
+pub const CALENDAR_TAB: usize = 0;
+pub const TODO_TAB: usize = 1;
+pub const TRASH_TAB: usize = 2;
+
 pub struct TabsState<'a> {
     pub titles: Vec<&'a str>,
     pub index: usize,
@@ -78,45 +90,403 @@ pub enum InputMode {
     Normal,
     Input,
     Select,
+    TodoInput,
+    Goto,
+    Search,
+}
+
+/// Message shown on top of everything until a key is pressed
+pub struct Alert {
+    pub title: &'static str,
+    pub message: String,
+}
+
+/// Dates in `[from, to)` marked in the weeks as having events and all-day events
+pub(crate) struct MarkedDates {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub events: Vec<NaiveDate>,
+    pub all_day: Vec<NaiveDate>,
 }
 
 pub(crate) struct App<'a> {
     pub title: &'a str,
     pub should_quit: bool,
     pub enhanced_graphics: bool,
-    pub files: Files,
+    pub store: Box<dyn Store>,
+    pub config: Config,
     pub state_tabs: TabsState<'a>,
     pub state_calendar: CalendarState,
     pub state_events: EventViewState,
+    pub state_todos: TodoViewState,
+    pub state_search: SearchViewState,
+    pub state_trash: TrashViewState,
     pub state_popup: PopupState,
+    /// Dates marked in the weeks, `None` until loaded or after the events changed
+    pub marked_dates: Option<MarkedDates>,
     pub hint_text: String,
+    /// Message shown on top of everything until a key is pressed
+    pub alert: Option<Alert>,
     pub input_mode: Input,
+    reminders_checked: NaiveDateTime,
+    /// Version of the stored events when they were loaded last
+    data_version: Option<u64>,
+    /// File the config is reloaded from when it changes
+    config_path: Option<PathBuf>,
+    /// When the config file was modified before it was loaded last
+    config_modified: Option<SystemTime>,
 }
 
 impl<'a> App<'a> {
-    pub fn new(title: &'a str, enhanced_graphics: bool) -> App<'a> {
-        let files = Files::new().unwrap();
+    pub fn new<S: Store + 'static>(
+        title: &'a str,
+        enhanced_graphics: bool,
+        store: S,
+        config: Config,
+    ) -> App<'a> {
         let selected_date = Local::now().naive_local().date();
-        let events = files.get_events_on_date(selected_date);
 
-        App {
+        let mut app = App {
             title,
             should_quit: false,
-            state_tabs: TabsState::new(vec!["Calendar"]),
+            state_tabs: TabsState::new(vec!["Calendar", "Todo", "Trash"]),
             enhanced_graphics,
-            files,
+            store: Box::new(store),
+            config,
             state_calendar: CalendarState::new(selected_date),
-            state_events: EventViewState::new(None, events),
+            state_events: EventViewState::new(None, Vec::new()),
+            state_todos: TodoViewState::new(None, Vec::new()),
+            state_search: SearchViewState::default(),
+            state_trash: TrashViewState::new(None, Vec::new()),
             state_popup: PopupState::new(PopupInput::default()),
+            marked_dates: None,
             hint_text: String::new(),
+            alert: None,
             input_mode: Input::new(), //InputMode::Normal,
+            reminders_checked: Local::now().naive_local(),
+            data_version: None,
+            config_path: None,
+            config_modified: None,
+        };
+        app.apply_calendar_visibility();
+        app.purge_expired_trash();
+        app.refresh();
+        app.load_todos();
+        app
+    }
+
+    /// Reload the config from `path` whenever the file changes
+    pub fn watch_config(mut self, path: &Path) -> App<'a> {
+        self.config_modified = modified(path);
+        self.config_path = Some(path.to_path_buf());
+        self
+    }
+
+    /// Apply the config again if its file was changed since loaded last,
+    /// a config which fails to parse is reported and the current one is kept
+    pub fn reload_config(&mut self) {
+        let path = match &self.config_path {
+            Some(path) => path.clone(),
+            None => return,
+        };
+        let modified = modified(&path);
+        if modified.is_none() || modified == self.config_modified {
+            return;
+        }
+        self.config_modified = modified;
+
+        match fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| Config::parse(&text))
+        {
+            Ok(config) => {
+                info!("Reloaded {}", path.display());
+                self.config = config;
+                // Calendars toggled in the app stay as they are
+                self.apply_calendar_visibility();
+                self.load_events();
+            }
+            Err(e) => self.report(&format!("Could not reload {}", path.display()), e),
         }
     }
 
     // Update app states when the tick timout occurs
     pub fn on_tick(&mut self) {
-        self.state_events.events = self
-            .files
-            .get_events_on_date(self.state_calendar.get_selected_date());
+        self.refresh();
+        self.fire_reminders();
+    }
+
+    /// Load the events again if they were changed since loaded last,
+    /// here or by another process
+    pub fn refresh(&mut self) {
+        match self.store.data_version() {
+            Ok(version) if Some(version) == self.data_version => {}
+            Ok(version) => {
+                self.data_version = Some(version);
+                self.load_events();
+                if self.state_tabs.index == TRASH_TAB {
+                    self.load_trash();
+                }
+            }
+            Err(e) => self.report("Could not check for changes", e),
+        }
+    }
+
+    /// Show and hide the calendars as they were toggled last
+    pub fn apply_calendar_visibility(&mut self) {
+        match self.store.get_calendar_visibility() {
+            Ok(visibility) => self.config.apply_visibility(&visibility),
+            Err(e) => self.report("Could not load the calendars", e),
+        }
+    }
+
+    /// Load the events of the visible calendars on the selected date
+    /// and forget the marked dates to load them again
+    pub fn load_events(&mut self) {
+        self.marked_dates = None;
+        match self
+            .store
+            .get_events_on_date(self.state_calendar.get_selected_date())
+        {
+            Ok(events) => {
+                self.state_events.events = events
+                    .into_iter()
+                    .filter(|e| self.config.is_visible(e))
+                    .collect()
+            }
+            Err(e) => self.report("Could not load events", e),
+        }
+        // Events removed by other processes may leave the selection past the end
+        let last = self.state_events.events.len().checked_sub(1);
+        self.state_events.selected = self.state_events.selected.zip(last).map(|(i, l)| i.min(l));
+    }
+
+    pub fn load_todos(&mut self) {
+        match self.store.get_todos() {
+            Ok(todos) => self.state_todos.todos = todos,
+            Err(e) => self.report("Could not load todos", e),
+        }
+    }
+
+    /// Mark the dates in `[from, to)` with events of the visible calendars,
+    /// they are queried again only if the range or the events changed
+    pub fn load_marked_dates(&mut self, from: NaiveDate, to: NaiveDate) {
+        if matches!(&self.marked_dates, Some(m) if m.from == from && m.to == to) {
+            return;
+        }
+        let visible = |e: &CalEvent| self.config.is_visible(e);
+        let dates = self
+            .store
+            .get_dates_with(from, to, &visible)
+            .and_then(|events| {
+                let all_day = self
+                    .store
+                    .get_dates_with(from, to, &|e| e.is_all_day() && visible(e))?;
+                Ok(MarkedDates {
+                    from,
+                    to,
+                    events,
+                    all_day,
+                })
+            });
+        match dates {
+            Ok(dates) => self.marked_dates = Some(dates),
+            Err(e) => self.report("Could not load the calendar", e),
+        }
+    }
+
+    /// Load the deleted events keeping the selection in bounds
+    pub fn load_trash(&mut self) {
+        match self.store.get_trash() {
+            Ok(events) => self.state_trash.events = events,
+            Err(e) => self.report("Could not load the trash", e),
+        }
+        let len = self.state_trash.events.len();
+        let selected = match self.state_trash.selected {
+            _ if len == 0 => None,
+            Some(i) => Some(i.min(len - 1)),
+            None => Some(0),
+        };
+        self.state_trash.select(selected);
+    }
+
+    /// Delete for good the events kept in the trash longer than configured
+    fn purge_expired_trash(&mut self) {
+        if self.config.trash_days == 0 {
+            return;
+        }
+        let time = Local::now().naive_local() - ChronoDuration::days(self.config.trash_days.into());
+        match self.store.purge_trash(time) {
+            Ok(0) => {}
+            Ok(purged) => info!("Purged {} events from the trash", purged),
+            Err(e) => self.report("Could not empty the trash", e),
+        }
+        self.store.end_change();
+    }
+
+    /// Show a message, messages with the same title are shown together
+    pub fn alert(&mut self, title: &'static str, message: String) {
+        match &mut self.alert {
+            Some(alert) if alert.title == title => {
+                if !alert.message.lines().any(|l| l == message) {
+                    alert.message.push('\n');
+                    alert.message.push_str(&message);
+                }
+            }
+            _ => self.alert = Some(Alert { title, message }),
+        }
+    }
+
+    /// Log the error of a failed operation and show it to the user
+    pub fn report<E: Display>(&mut self, context: &str, error: E) {
+        error!("{}: {}", context, error);
+        self.alert("Error", format!("{}: {}", context, error));
+    }
+
+    /// Notify about the reminders gone off since the last check
+    fn fire_reminders(&mut self) {
+        let now = Local::now().naive_local();
+        let reminders = match self
+            .store
+            .get_reminders_between(self.reminders_checked, now)
+        {
+            Ok(reminders) => reminders,
+            Err(e) => return self.report("Could not check reminders", e),
+        };
+        self.reminders_checked = now;
+
+        // Hidden calendars stay quiet like they stay out of the view
+        let events: Vec<CalEvent> = reminders
+            .into_iter()
+            .map(|(_, event)| event)
+            .filter(|e| self.config.is_visible(e))
+            .collect();
+        for event in events {
+            let message = reminder_message(&event);
+            if let Some(command) = self.config.notify_command.clone() {
+                if let Err(e) = notify(&command, &message) {
+                    self.report("Could not run the notification command", e);
+                }
+            }
+            self.alert("Reminder", message);
+        }
+    }
+}
+
+/// Time the file was modified, `None` if it can not be read
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        files::tests::temp_paths,
+        store::{memory::MemoryStore, EventStore},
+    };
+
+    #[test]
+    fn app_shows_events_of_its_store() {
+        let mut store = MemoryStore::new();
+        let now = Local::now().naive_local();
+        store
+            .add_event(CalEvent::new(
+                None,
+                String::from("Standup"),
+                now,
+                now + ChronoDuration::minutes(15),
+            ))
+            .unwrap();
+
+        let mut app = App::new("RayDay", false, store, Config::default());
+        assert_eq!(app.state_events.events.len(), 1);
+
+        let id = app.state_events.events[0].id().unwrap();
+        app.store.remove_event(id).unwrap();
+        app.on_tick();
+        assert!(app.state_events.events.is_empty());
+    }
+
+    #[test]
+    fn reminders_of_hidden_calendars_stay_quiet() {
+        let mut store = MemoryStore::new();
+        let now = Local::now().naive_local();
+        let start = now + ChronoDuration::minutes(5);
+        store
+            .add_event(
+                CalEvent::new(None, String::from("Standup"), start, start).with_reminders(vec![10]),
+            )
+            .unwrap();
+        let mut app = App::new("RayDay", false, store, Config::default());
+
+        app.config.toggle_calendar(0);
+        app.reminders_checked = now - ChronoDuration::minutes(10);
+        app.fire_reminders();
+        assert!(app.alert.is_none());
+
+        app.config.toggle_calendar(0);
+        app.reminders_checked = now - ChronoDuration::minutes(10);
+        app.fire_reminders();
+        assert_eq!(app.alert.unwrap().title, "Reminder");
+    }
+
+    #[test]
+    fn selection_stays_in_bounds_after_external_changes() {
+        let mut store = MemoryStore::new();
+        let now = Local::now().naive_local();
+        for desc in ["Standup", "Review"] {
+            store
+                .add_event(CalEvent::new(None, String::from(desc), now, now))
+                .unwrap();
+        }
+        let mut app = App::new("RayDay", false, store, Config::default());
+        app.state_events.selected = Some(1);
+
+        let id = app.state_events.events[1].id().unwrap();
+        app.store.remove_event(id).unwrap();
+        app.on_tick();
+        assert_eq!(app.state_events.selected, Some(0));
+
+        let id = app.state_events.events[0].id().unwrap();
+        app.store.remove_event(id).unwrap();
+        app.on_tick();
+        assert_eq!(app.state_events.selected, None);
+    }
+
+    #[test]
+    fn config_is_reloaded_when_changed() {
+        let paths = temp_paths("config");
+        let path = paths.config.clone();
+        Config::default().save(&path).unwrap();
+        let mut app =
+            App::new("RayDay", false, MemoryStore::new(), Config::default()).watch_config(&path);
+        // The modification times are set apart not to depend on their precision
+        let write = |text: String, seconds: u64| {
+            fs::write(&path, text).unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(SystemTime::now() + Duration::from_secs(seconds))
+                .unwrap();
+        };
+
+        let text = fs::read_to_string(&path).unwrap();
+        write(text.replace("trash_days: 30", "trash_days: x"), 10);
+        app.reload_config();
+        let alert = app.alert.take().unwrap();
+        assert!(alert.message.contains("line"), "{}", alert.message);
+        assert_eq!(app.config.trash_days, 30);
+
+        write(text.replace("trash_days: 30", "trash_days: 7"), 20);
+        app.reload_config();
+        assert!(app.alert.is_none());
+        assert_eq!(app.config.trash_days, 7);
+
+        app.store.set_calendar_visible("default", false).unwrap();
+        write(text.replace("trash_days: 30", "trash_days: 8"), 30);
+        app.reload_config();
+        assert!(!app.config.calendars[0].visible);
     }
 }
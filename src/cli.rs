@@ -1,8 +1,15 @@
-use centered_interval_tree::CenteredIntervalTree;
-use chrono::{Local, NaiveTime};
-use clap::{Arg, Parser, Subcommand};
+use std::path::PathBuf;
 
-use crate::{app::run, files::Files};
+use anyhow::{anyhow, Result};
+use centered_interval_tree::{interval::Interval, CenteredIntervalTree};
+use chrono::{Local, NaiveDateTime};
+use clap::{Parser, Subcommand};
+
+use crate::{
+    event::{format_reminders, format_tags, parse_tags, EditScope, Event},
+    files::{Config, Files, Paths},
+    store::{EventStore, StorageError},
+};
 
 #[derive(Parser)]
 #[command(name = "RayDay")]
@@ -10,42 +17,311 @@ use crate::{app::run, files::Files};
 #[command(version = "0.1.0")]
 #[command(about = "Task tracker and calendar combined", long_about = None)]
 pub(crate) struct RaydayCli {
+    /// Path of the events database, overrides $RAYDAY_DB
+    #[arg(long, global = true)]
+    pub db: Option<PathBuf>,
+    /// Path of the config file, overrides $RAYDAY_CONFIG
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
     #[command(subcommand)]
     pub command: Option<RaydayCommand>,
 }
 
 #[derive(Subcommand)]
 pub(crate) enum RaydayCommand {
+    /// Print the events taking place now
     Now,
+    /// Print the details of an event
+    Show { id: usize },
+    /// Change the fields of an event, all occurrences of a recurring one
+    Edit {
+        id: usize,
+        #[arg(long)]
+        description: Option<String>,
+        /// Start as "YYYY-MM-DD HH:MM"
+        #[arg(long, value_parser = parse_datetime)]
+        start: Option<NaiveDateTime>,
+        /// End as "YYYY-MM-DD HH:MM"
+        #[arg(long, value_parser = parse_datetime)]
+        end: Option<NaiveDateTime>,
+        /// Comma separated tags
+        #[arg(long)]
+        tags: Option<String>,
+        #[arg(long)]
+        location: Option<String>,
+        #[arg(long)]
+        notes: Option<String>,
+        #[arg(long)]
+        url: Option<String>,
+        /// Name of the calendar to move the event to
+        #[arg(long)]
+        calendar: Option<String>,
+    },
+    /// Move an event to the trash, all occurrences of a recurring one
+    Delete { id: usize },
+    /// List the calendars, or show or hide the events of one of them
+    Calendars {
+        #[arg(long, conflicts_with = "hide")]
+        show: Option<String>,
+        #[arg(long)]
+        hide: Option<String>,
+    },
+    /// Take a snapshot of the database
+    Backup,
+    /// List the snapshots of the database or roll it back to one of them
+    Restore {
+        /// Number of the snapshot in the list, 1 is the latest
+        snapshot: Option<usize>,
+    },
+    /// Find events by the words of their description or notes, best matches first
+    Search {
+        #[arg(required = true)]
+        words: Vec<String>,
+    },
     // Add,
-    // Remove,
 }
 
 impl RaydayCli {
-    pub(crate) fn handle_command(&self) {
+    pub(crate) fn handle_command(&self, paths: &Paths) -> Result<()> {
         match self.command {
             Some(RaydayCommand::Now) => {
-                let files = Files::new().unwrap();
+                let files = Files::new(paths)?.with_source("cli");
+                let mut config = Config::load(&paths.config)?;
+                config.apply_visibility(&files.get_calendar_visibility()?);
                 let now = Local::now().naive_local();
-                let events = files.cache_events();
+                let events_today = files.get_events_on_date(now.date())?;
+
+                let mut tree = CenteredIntervalTree::<NaiveDateTime, String>::new();
 
-                let mut tree = CenteredIntervalTree::<NaiveTime, String>::new();
+                for event in events_today.iter().filter(|e| config.is_visible(e)) {
+                    let interval = Interval::new(event.local_start(), event.local_end());
+                    tree.add(interval, format_event(event));
+                }
 
-                if let Some(events_today) = events.get(&now.date()) {
-                    for event in events_today.iter() {
-                        let time = event.time();
-                        let interval = (time.start_datetime(), time.end_datetime());
-                        tree.add(interval, event.desc());
-                    }
+                let todo_now = tree.search(now);
+
+                for todo in todo_now {
+                    println!("{}", &todo);
+                }
+            }
+            Some(RaydayCommand::Show { id }) => {
+                let files = Files::new(paths)?.with_source("cli");
+                let event = files.get_event(id)?.ok_or(StorageError::NotFound(id))?;
+                println!("{}", describe_event(&event));
+            }
+            Some(RaydayCommand::Edit {
+                id,
+                ref description,
+                start,
+                end,
+                ref tags,
+                ref location,
+                ref notes,
+                ref url,
+                ref calendar,
+            }) => {
+                let mut files = Files::new(paths)?.with_source("cli");
+                let event = files.get_event(id)?.ok_or(StorageError::NotFound(id))?;
+                if let Some(calendar) = calendar {
+                    Config::load(&paths.config)?.check_calendar(calendar)?;
+                }
 
-                    let todo_now = tree.search(now.time());
+                // Moving the start keeps the duration unless the end is given
+                let start = start.unwrap_or(event.start());
+                let end = end.unwrap_or(start + (event.end() - event.start()));
+                if end < start {
+                    return Err(anyhow!("The event can not end before it starts"));
+                }
 
-                    for todo in todo_now {
-                        println!("{}", &todo);
-                    }
+                let edited = Event::new(
+                    Some(id),
+                    description.clone().unwrap_or(event.desc()),
+                    start,
+                    end,
+                )
+                .with_all_day(event.is_all_day())
+                .with_timezone(event.timezone())
+                .with_tags(
+                    tags.as_deref()
+                        .map(parse_tags)
+                        .unwrap_or(event.tags().to_vec()),
+                )
+                .with_location(location.clone().or(event.location().map(String::from)))
+                .with_notes(notes.clone().or(event.notes().map(String::from)))
+                .with_url(url.clone().or(event.url().map(String::from)))
+                .with_calendar(calendar.clone().unwrap_or(event.calendar().to_string()))
+                .with_reminders(event.reminders().to_vec())
+                .with_rrule(event.rrule().cloned())
+                .with_exdates(event.exdates().to_vec())
+                .with_master(event.master_id(), event.recurrence_id());
+                files.update_event(edited.clone())?;
+                println!("{}", describe_event(&edited));
+            }
+            Some(RaydayCommand::Delete { id }) => {
+                let mut files = Files::new(paths)?.with_source("cli");
+                let event = files.get_event(id)?.ok_or(StorageError::NotFound(id))?;
+                // Overrides go alone, recurring events go with their overrides
+                match event.master_id() {
+                    Some(_) => files.remove_event(id)?,
+                    None => files.remove_event_in_scope(&event, EditScope::All)?,
+                }
+                println!("Moved to the trash: {}", format_event(&event));
+            }
+            Some(RaydayCommand::Search { ref words }) => {
+                let files = Files::new(paths)?.with_source("cli");
+                let mut config = Config::load(&paths.config)?;
+                config.apply_visibility(&files.get_calendar_visibility()?);
+                for event in files
+                    .search_events(&words.join(" "))?
+                    .into_iter()
+                    .filter(|e| config.is_visible(e))
+                {
+                    let repeat = match event.rrule() {
+                        Some(rrule) => format!(" (repeats {})", rrule),
+                        None => String::new(),
+                    };
+                    println!(
+                        "{:>5} {} {}{}",
+                        event.id().unwrap_or_default(),
+                        event.local_start().format("%Y-%m-%d"),
+                        format_event(&event),
+                        repeat
+                    );
+                }
+            }
+            Some(RaydayCommand::Calendars { ref show, ref hide }) => {
+                // The visibility is kept in the database, the config is left as written
+                let mut files = Files::new(paths)?.with_source("cli");
+                let mut config = Config::load(&paths.config)?;
+                if let Some((name, visible)) = show
+                    .as_ref()
+                    .map(|n| (n, true))
+                    .or(hide.as_ref().map(|n| (n, false)))
+                {
+                    config.check_calendar(name)?;
+                    files.set_calendar_visible(name, visible)?;
+                }
+                config.apply_visibility(&files.get_calendar_visibility()?);
+                for calendar in config.calendars.iter() {
+                    println!(
+                        "{}{}",
+                        calendar.name,
+                        if calendar.visible { "" } else { " (hidden)" }
+                    );
+                }
+            }
+            Some(RaydayCommand::Backup) => {
+                let files = Files::new(paths)?;
+                let snapshot = files.backup("manual")?;
+                println!("Saved {}", snapshot.path.display());
+            }
+            Some(RaydayCommand::Restore { snapshot: None }) => {
+                for (i, snapshot) in Files::snapshots(paths)?.iter().enumerate() {
+                    println!(
+                        "{:>3} {} {}",
+                        i + 1,
+                        snapshot.taken_at.format("%Y-%m-%d %H:%M:%S"),
+                        snapshot.reason
+                    );
+                }
+            }
+            Some(RaydayCommand::Restore {
+                snapshot: Some(number),
+            }) => {
+                // The database isn't opened with `Files::new` in case it can't be migrated
+                let snapshot = number
+                    .checked_sub(1)
+                    .and_then(|i| Files::snapshots(paths).ok()?.get(i).cloned())
+                    .ok_or_else(|| anyhow!("No snapshot number {}", number))?;
+                let taken_at = snapshot.taken_at.format("%Y-%m-%d %H:%M:%S");
+                match Files::restore(paths, &snapshot)? {
+                    Some(before) => println!(
+                        "Rolled back to {}, the previous state is saved in {}",
+                        taken_at,
+                        before.path.display()
+                    ),
+                    None => println!("Rolled back to {}", taken_at),
                 }
             }
-            None | Some(_) => {}
+            None => {}
         }
+        Ok(())
+    }
+}
+
+/// Parse the date and time given in the command line
+fn parse_datetime(s: &str) -> Result<NaiveDateTime, String> {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M")
+        .map_err(|_| format!("expected \"YYYY-MM-DD HH:MM\", got \"{}\"", s))
+}
+
+/// Format all the fields of the event, one per line
+fn describe_event(event: &Event) -> String {
+    let mut lines = vec![
+        format!("Id: {}", event.id().unwrap_or_default()),
+        format!("Description: {}", event.desc()),
+        format!("Calendar: {}", event.calendar()),
+    ];
+    if event.is_all_day() {
+        lines.push(format!(
+            "Time: all day {}",
+            event.start().format("%Y-%m-%d")
+        ));
+    } else {
+        lines.push(format!(
+            "Time: {} - {}",
+            event.local_start().format("%Y-%m-%d %H:%M"),
+            event.local_end().format("%Y-%m-%d %H:%M")
+        ));
+    }
+    if let Some(tz) = event.timezone() {
+        lines.push(format!("Time zone: {}", tz.name()));
+    }
+    if let Some(rrule) = event.rrule() {
+        lines.push(format!("Repeat: {}", rrule));
+    }
+    if let Some(master_id) = event.master_id() {
+        lines.push(format!("Occurrence of: {}", master_id));
+    }
+    if !event.tags().is_empty() {
+        lines.push(format!("Tags: {}", format_tags(event.tags())));
+    }
+    if !event.reminders().is_empty() {
+        lines.push(format!(
+            "Remind: {} min before",
+            format_reminders(event.reminders())
+        ));
+    }
+    if let Some(location) = event.location() {
+        lines.push(format!("Location: {}", location));
+    }
+    if let Some(url) = event.url() {
+        lines.push(format!("URL: {}", url));
+    }
+    if let Some(notes) = event.notes() {
+        lines.push(format!("Notes:\n{}", notes));
+    }
+    lines.join("\n")
+}
+
+/// Format the event in the local time, noting the time in its own zone
+fn format_event(event: &Event) -> String {
+    if event.is_all_day() {
+        return format!("all day {}", event.desc());
+    }
+
+    let mut line = format!(
+        "{}-{} {}",
+        event.local_start().format("%H:%M"),
+        event.local_end().format("%H:%M"),
+        event.desc()
+    );
+    if let Some(tz) = event.timezone() {
+        line.push_str(&format!(
+            " ({} {})",
+            event.start().format("%H:%M"),
+            tz.name()
+        ));
     }
+    line
 }
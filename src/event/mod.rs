@@ -1,8 +1,12 @@
 pub(crate) mod iterator;
+pub(crate) mod rrule;
 
 use chrono::{prelude::*, Duration};
+use chrono_tz::Tz;
 
 use serde::{Deserialize, Serialize};
+
+use self::rrule::RRule;
 use std::{
     borrow::Borrow,
     cmp,
@@ -13,13 +17,85 @@ use std::{
 const PARSE_DATE: &str = "%Y-%m-%d";
 const PARSE_TIME: &str = "%H:%M:%S";
 
-enum Periodicity {
+/// Reminders are at most a day before the event
+pub const MAX_REMINDER_MINUTES: u32 = 24 * 60;
+
+/// Calendar of the events saved before there were several
+pub const DEFAULT_CALENDAR: &str = "default";
+
+fn default_calendar() -> String {
+    String::from(DEFAULT_CALENDAR)
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Periodicity {
     Daily,
     Weekly,
     Monthly,
     Yearly,
 }
 
+impl Display for Periodicity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Periodicity::Daily => "daily",
+            Periodicity::Weekly => "weekly",
+            Periodicity::Monthly => "monthly",
+            Periodicity::Yearly => "yearly",
+        })
+    }
+}
+
+impl FromStr for Periodicity {
+    type Err = EventError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "daily" => Ok(Periodicity::Daily),
+            "weekly" => Ok(Periodicity::Weekly),
+            "monthly" => Ok(Periodicity::Monthly),
+            "yearly" => Ok(Periodicity::Yearly),
+            _ => Err(EventError::Parsing),
+        }
+    }
+}
+
+/// Occurrences of a recurring event affected by an edit or removal
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum EditScope {
+    This,
+    Following,
+    All,
+}
+
+impl EditScope {
+    pub fn next(&self) -> EditScope {
+        match self {
+            EditScope::This => EditScope::Following,
+            EditScope::Following => EditScope::All,
+            EditScope::All => EditScope::This,
+        }
+    }
+
+    pub fn previous(&self) -> EditScope {
+        match self {
+            EditScope::This => EditScope::All,
+            EditScope::Following => EditScope::This,
+            EditScope::All => EditScope::Following,
+        }
+    }
+}
+
+impl Display for EditScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            EditScope::This => "This occurrence",
+            EditScope::Following => "This and following",
+            EditScope::All => "All occurrences",
+        })
+    }
+}
+
 pub trait Today {
     fn today(hours: u32, minutes: u32, d: Duration) -> EventTime;
     fn now(d: Duration) -> EventTime;
@@ -125,6 +201,30 @@ pub struct Event {
     start: NaiveDateTime,
     end: NaiveDateTime,
     description: String,
+    rrule: Option<RRule>,
+    exdates: Vec<NaiveDate>,
+    master_id: Option<usize>,
+    recurrence_id: Option<NaiveDate>,
+    all_day: bool,
+    timezone: Option<Tz>,
+    tags: Vec<String>,
+    location: Option<String>,
+    notes: Option<String>,
+    url: Option<String>,
+    reminders: Vec<u32>,
+    /// Name of the calendar the event belongs to
+    #[serde(default = "default_calendar")]
+    calendar: String,
+    /// When the event was moved to the trash
+    #[serde(default)]
+    deleted_at: Option<NaiveDateTime>,
+    #[serde(default)]
+    created_at: Option<NaiveDateTime>,
+    #[serde(default)]
+    updated_at: Option<NaiveDateTime>,
+    /// Tool which saved the event last, e.g. tui or cli
+    #[serde(default)]
+    updated_by: Option<String>,
 }
 
 impl Event {
@@ -139,9 +239,127 @@ impl Event {
             start,
             end,
             description,
+            rrule: None,
+            exdates: Vec::new(),
+            master_id: None,
+            recurrence_id: None,
+            all_day: false,
+            timezone: None,
+            tags: Vec::new(),
+            location: None,
+            notes: None,
+            url: None,
+            reminders: Vec::new(),
+            calendar: default_calendar(),
+            deleted_at: None,
+            created_at: None,
+            updated_at: None,
+            updated_by: None,
         }
     }
 
+    /// Create an all-day event lasting from the `first` to the `last` date inclusive
+    pub fn new_all_day(
+        id: Option<usize>,
+        description: String,
+        first: NaiveDate,
+        last: NaiveDate,
+    ) -> Event {
+        let start = first.and_hms_opt(0, 0, 0).unwrap();
+        let end = last.and_hms_opt(0, 0, 0).unwrap() + Duration::days(1);
+        Event::new(id, description, start, end).with_all_day(true)
+    }
+
+    pub fn with_id(mut self, id: Option<usize>) -> Event {
+        self.id = id;
+        self
+    }
+
+    pub fn with_all_day(mut self, all_day: bool) -> Event {
+        self.all_day = all_day;
+        self
+    }
+
+    /// Set the zone of the event's start and end, `None` is the viewer's zone
+    pub fn with_timezone(mut self, timezone: Option<Tz>) -> Event {
+        self.timezone = timezone;
+        self
+    }
+
+    /// Set the tags of the event, e.g. meeting or personal
+    pub fn with_tags(mut self, tags: Vec<String>) -> Event {
+        self.tags = tags;
+        self
+    }
+
+    pub fn with_location(mut self, location: Option<String>) -> Event {
+        self.location = location;
+        self
+    }
+
+    /// Set the notes of the event, they may span several lines
+    pub fn with_notes(mut self, notes: Option<String>) -> Event {
+        self.notes = notes;
+        self
+    }
+
+    pub fn with_url(mut self, url: Option<String>) -> Event {
+        self.url = url;
+        self
+    }
+
+    /// Move the event to the calendar with `calendar` name, e.g. work or personal
+    pub fn with_calendar(mut self, calendar: String) -> Event {
+        self.calendar = calendar;
+        self
+    }
+
+    /// Set the reminders in minutes before the start of the event
+    pub fn with_reminders(mut self, reminders: Vec<u32>) -> Event {
+        self.reminders = reminders;
+        self
+    }
+
+    pub fn with_rrule(mut self, rrule: Option<RRule>) -> Event {
+        self.rrule = rrule;
+        self
+    }
+
+    pub fn with_exdates(mut self, exdates: Vec<NaiveDate>) -> Event {
+        self.exdates = exdates;
+        self
+    }
+
+    pub fn with_deleted_at(mut self, deleted_at: Option<NaiveDateTime>) -> Event {
+        self.deleted_at = deleted_at;
+        self
+    }
+
+    /// Set when the event was saved first and last, and by which tool
+    pub fn with_timestamps(
+        mut self,
+        created_at: Option<NaiveDateTime>,
+        updated_at: Option<NaiveDateTime>,
+        updated_by: Option<String>,
+    ) -> Event {
+        self.created_at = created_at;
+        self.updated_at = updated_at;
+        self.updated_by = updated_by;
+        self
+    }
+
+    /// Make the event an override of the occurrence on `recurrence_id`
+    /// of the recurring event with `master_id`
+    pub fn with_master(
+        mut self,
+        master_id: Option<usize>,
+        recurrence_id: Option<NaiveDate>,
+    ) -> Event {
+        self.master_id = master_id;
+        self.recurrence_id = recurrence_id;
+        self
+    }
+
     pub fn id(&self) -> Option<usize> {
         self.id
     }
@@ -158,6 +376,168 @@ impl Event {
         self.description.to_string()
     }
 
+    pub fn rrule(&self) -> Option<&RRule> {
+        self.rrule.as_ref()
+    }
+
+    pub fn exdates(&self) -> &[NaiveDate] {
+        &self.exdates
+    }
+
+    pub fn exdates_mut(&mut self) -> &mut Vec<NaiveDate> {
+        &mut self.exdates
+    }
+
+    pub fn master_id(&self) -> Option<usize> {
+        self.master_id
+    }
+
+    pub fn recurrence_id(&self) -> Option<NaiveDate> {
+        self.recurrence_id
+    }
+
+    pub fn is_all_day(&self) -> bool {
+        self.all_day
+    }
+
+    pub fn timezone(&self) -> Option<Tz> {
+        self.timezone
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn location(&self) -> Option<&str> {
+        self.location.as_deref()
+    }
+
+    pub fn notes(&self) -> Option<&str> {
+        self.notes.as_deref()
+    }
+
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    pub fn deleted_at(&self) -> Option<NaiveDateTime> {
+        self.deleted_at
+    }
+
+    pub fn created_at(&self) -> Option<NaiveDateTime> {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> Option<NaiveDateTime> {
+        self.updated_at
+    }
+
+    pub fn updated_by(&self) -> Option<&str> {
+        self.updated_by.as_deref()
+    }
+
+    pub fn reminders(&self) -> &[u32] {
+        &self.reminders
+    }
+
+    pub fn calendar(&self) -> &str {
+        &self.calendar
+    }
+
+    /// Local times when the reminders of the event go off
+    pub fn reminder_times(&self) -> Vec<NaiveDateTime> {
+        self.reminders
+            .iter()
+            .map(|m| self.local_start() - Duration::minutes(*m as i64))
+            .collect()
+    }
+
+    /// Start of the event in the viewer's local time
+    pub fn local_start(&self) -> NaiveDateTime {
+        self.start_in(&Local)
+    }
+
+    /// End of the event in the viewer's local time
+    pub fn local_end(&self) -> NaiveDateTime {
+        self.end_in(&Local)
+    }
+
+    pub fn start_in<Z: TimeZone>(&self, zone: &Z) -> NaiveDateTime {
+        self.convert(self.start, zone)
+    }
+
+    pub fn end_in<Z: TimeZone>(&self, zone: &Z) -> NaiveDateTime {
+        self.convert(self.end, zone)
+    }
+
+    /// Convert the time of the event to the zone, all-day events keep their dates
+    fn convert<Z: TimeZone>(&self, time: NaiveDateTime, zone: &Z) -> NaiveDateTime {
+        match self.timezone {
+            Some(tz) if !self.all_day => tz
+                .from_local_datetime(&time)
+                .earliest()
+                .map(|t| t.with_timezone(zone).naive_local())
+                .unwrap_or(time),
+            _ => time,
+        }
+    }
+
+    /// Check if the event is a part of a recurring series
+    pub fn is_recurring(&self) -> bool {
+        self.rrule.is_some() || self.master_id.is_some()
+    }
+
+    /// Date of the occurrence in its series, overrides keep the original date
+    pub fn occurrence_date(&self) -> NaiveDate {
+        self.recurrence_id.unwrap_or(self.start.date())
+    }
+
+    /// Get the occurrence of the event on the given date, if there is one
+    pub fn occurrence_on(&self, date: NaiveDate) -> Option<Event> {
+        self.occurrences_between(date, date.succ_opt()?)
+            .into_iter()
+            .next()
+    }
+
+    /// Check if the event takes place in range `[from, to)` of the local time
+    pub fn overlaps(&self, from: NaiveDateTime, to: NaiveDateTime) -> bool {
+        let (start, end) = (self.local_start(), self.local_end());
+        start < to && (end > from || start >= from)
+    }
+
+    /// Get the occurrences of the event overlapping local days in range `[from, to)`.
+    /// Recurring events are moved to the dates keeping their time and duration.
+    pub fn occurrences_between(&self, from: NaiveDate, to: NaiveDate) -> Vec<Event> {
+        let from_datetime = from.and_hms_opt(0, 0, 0).unwrap();
+        let to_datetime = to.and_hms_opt(0, 0, 0).unwrap();
+
+        let rrule = match &self.rrule {
+            Some(rrule) => rrule,
+            None if self.overlaps(from_datetime, to_datetime) => return vec![self.clone()],
+            None => return vec![],
+        };
+
+        // Occurrences started before the range may last into it, and the
+        // dates in the event's zone may differ from the local ones by a day
+        let duration = self.end - self.start;
+        let first = (from_datetime - duration - Duration::days(1)).date();
+        let last = to.succ_opt().unwrap_or(to);
+        rrule
+            .occurrences(self.start, first, last)
+            .into_iter()
+            .filter(|date| !self.exdates.contains(date))
+            .map(|date| {
+                let start = NaiveDateTime::new(date, self.start.time());
+                Event {
+                    start,
+                    end: start + duration,
+                    ..self.clone()
+                }
+            })
+            .filter(|e| e.overlaps(from_datetime, to_datetime))
+            .collect()
+    }
+
     pub fn to_string(&self) -> String {
         format!(
             "{}|{}|{}",
@@ -168,6 +548,40 @@ impl Event {
     }
 }
 
+/// Format tags as a comma separated list
+pub fn format_tags(tags: &[String]) -> String {
+    tags.join(",")
+}
+
+/// Parse a comma separated list of tags, ignoring empty ones
+pub fn parse_tags(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// Format reminder offsets as a comma separated list of minutes
+pub fn format_reminders(reminders: &[u32]) -> String {
+    reminders
+        .iter()
+        .map(|m| m.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Parse a comma separated list of minutes, each at most a day
+pub fn parse_reminders(s: &str) -> Result<Vec<u32>, EventError> {
+    s.split(',')
+        .map(|m| m.trim())
+        .filter(|m| !m.is_empty())
+        .map(|m| match m.parse::<u32>() {
+            Ok(m) if m <= MAX_REMINDER_MINUTES => Ok(m),
+            _ => Err(EventError::Parsing),
+        })
+        .collect()
+}
+
 impl FromStr for Event {
     type Err = EventError;
 
@@ -185,6 +599,22 @@ impl FromStr for Event {
             description,
             start,
             end,
+            rrule: None,
+            exdates: Vec::new(),
+            master_id: None,
+            recurrence_id: None,
+            all_day: false,
+            timezone: None,
+            tags: Vec::new(),
+            location: None,
+            notes: None,
+            url: None,
+            reminders: Vec::new(),
+            calendar: default_calendar(),
+            deleted_at: None,
+            created_at: None,
+            updated_at: None,
+            updated_by: None,
         };
         Ok(event)
     }
@@ -275,4 +705,182 @@ mod tests {
 
         assert_eq!(EventTime::from("00:00:00-01:00:00"), time);
     }
+
+    fn datetime(y: i32, m: u32, d: u32, h: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn periodicity_parsing() {
+        assert_eq!(
+            "weekly".parse::<Periodicity>().unwrap(),
+            Periodicity::Weekly
+        );
+        assert_eq!(
+            Periodicity::Monthly
+                .to_string()
+                .parse::<Periodicity>()
+                .unwrap(),
+            Periodicity::Monthly
+        );
+        assert!("fortnightly".parse::<Periodicity>().is_err());
+    }
+
+    #[test]
+    fn weekly_event_occurrences() {
+        let event = Event::new(
+            Some(1),
+            String::from("Standup"),
+            datetime(2023, 7, 18, 10),
+            datetime(2023, 7, 18, 11),
+        )
+        .with_rrule(Some(RRule::new(Periodicity::Weekly)));
+
+        let next = event
+            .occurrence_on(NaiveDate::from_ymd_opt(2023, 7, 25).unwrap())
+            .unwrap();
+        assert_eq!(next.start(), datetime(2023, 7, 25, 10));
+        assert_eq!(next.end(), datetime(2023, 7, 25, 11));
+        assert_eq!(next.id(), Some(1));
+
+        assert!(event
+            .occurrence_on(NaiveDate::from_ymd_opt(2023, 7, 26).unwrap())
+            .is_none());
+        assert!(event
+            .occurrence_on(NaiveDate::from_ymd_opt(2023, 7, 11).unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn monthly_event_skips_short_months() {
+        let event = Event::new(
+            None,
+            String::from("Rent"),
+            datetime(2023, 1, 31, 9),
+            datetime(2023, 1, 31, 10),
+        )
+        .with_rrule(Some(RRule::new(Periodicity::Monthly)));
+
+        assert!(event
+            .occurrence_on(NaiveDate::from_ymd_opt(2023, 2, 28).unwrap())
+            .is_none());
+        assert!(event
+            .occurrence_on(NaiveDate::from_ymd_opt(2023, 3, 31).unwrap())
+            .is_some());
+    }
+
+    #[test]
+    fn event_crossing_midnight() {
+        let event = Event::new(
+            None,
+            String::from("Night shift"),
+            datetime(2023, 7, 18, 22),
+            datetime(2023, 7, 19, 2),
+        );
+
+        let date = |d| NaiveDate::from_ymd_opt(2023, 7, d).unwrap();
+        assert!(event.occurrence_on(date(18)).is_some());
+        assert!(event.occurrence_on(date(19)).is_some());
+        assert!(event.occurrence_on(date(20)).is_none());
+    }
+
+    #[test]
+    fn event_time_zone_conversion() {
+        let event = Event::new(
+            None,
+            String::from("Sync"),
+            datetime(2023, 7, 18, 16),
+            datetime(2023, 7, 18, 17),
+        )
+        .with_timezone(Some(chrono_tz::Europe::Berlin));
+
+        assert_eq!(
+            event.start_in(&chrono_tz::America::New_York),
+            datetime(2023, 7, 18, 10)
+        );
+        assert_eq!(event.end_in(&chrono_tz::UTC), datetime(2023, 7, 18, 15));
+
+        let floating = Event::new(
+            None,
+            String::from("Lunch"),
+            datetime(2023, 7, 18, 12),
+            datetime(2023, 7, 18, 13),
+        );
+        assert_eq!(
+            floating.start_in(&chrono_tz::America::New_York),
+            datetime(2023, 7, 18, 12)
+        );
+    }
+
+    #[test]
+    fn multi_day_all_day_event() {
+        let event = Event::new_all_day(
+            None,
+            String::from("Conference"),
+            NaiveDate::from_ymd_opt(2023, 7, 18).unwrap(),
+            NaiveDate::from_ymd_opt(2023, 7, 20).unwrap(),
+        );
+
+        for day in 18..=20 {
+            assert!(event
+                .occurrence_on(NaiveDate::from_ymd_opt(2023, 7, day).unwrap())
+                .is_some());
+        }
+        assert!(event
+            .occurrence_on(NaiveDate::from_ymd_opt(2023, 7, 21).unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn tags_parsing() {
+        let tags = parse_tags(" meeting, ,focus ");
+        assert_eq!(tags, vec![String::from("meeting"), String::from("focus")]);
+        assert_eq!(format_tags(&tags), "meeting,focus");
+        assert!(parse_tags("").is_empty());
+    }
+
+    #[test]
+    fn reminder_times() {
+        let event = Event::new(
+            None,
+            String::from("Standup"),
+            datetime(2023, 7, 18, 10),
+            datetime(2023, 7, 18, 11),
+        )
+        .with_reminders(parse_reminders("10, 60").unwrap());
+
+        assert_eq!(format_reminders(event.reminders()), "10,60");
+        assert_eq!(
+            event.reminder_times(),
+            vec![
+                datetime(2023, 7, 18, 10) - Duration::minutes(10),
+                datetime(2023, 7, 18, 9)
+            ]
+        );
+        assert!(parse_reminders("2000").is_err());
+    }
+
+    #[test]
+    fn excluded_occurrences() {
+        let event = Event::new(
+            None,
+            String::from("Gym"),
+            datetime(2023, 7, 17, 18),
+            datetime(2023, 7, 17, 19),
+        )
+        .with_rrule(Some(RRule::new(Periodicity::Daily)))
+        .with_exdates(vec![NaiveDate::from_ymd_opt(2023, 7, 19).unwrap()]);
+
+        let occurrences = event.occurrences_between(
+            NaiveDate::from_ymd_opt(2023, 7, 18).unwrap(),
+            NaiveDate::from_ymd_opt(2023, 7, 21).unwrap(),
+        );
+        assert_eq!(
+            occurrences.iter().map(|e| e.start()).collect::<Vec<_>>(),
+            vec![datetime(2023, 7, 18, 18), datetime(2023, 7, 20, 18)]
+        );
+    }
 }
@@ -0,0 +1,467 @@
+use chrono::{prelude::*, Duration};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+use super::{EventError, Periodicity};
+
+const PARSE_RRULE_DATE: &str = "%Y%m%d";
+const PARSE_RRULE_DATETIME: &str = "%Y%m%dT%H%M%S";
+
+/// Weekday of the `BYDAY` rule part, `nth` is the optional ordinal
+/// of the weekday in the month or year (negative counts from the end)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Copy, Clone)]
+pub struct ByDay {
+    pub nth: Option<i32>,
+    pub weekday: Weekday,
+}
+
+impl ByDay {
+    /// Check if the weekday matches the date, `position` and `from_end` are
+    /// the ordinals of the date's weekday in the month or year
+    fn matches(&self, date: NaiveDate, position: i32, from_end: i32) -> bool {
+        date.weekday() == self.weekday
+            && match self.nth {
+                Some(n) => n == position || n == from_end,
+                None => true,
+            }
+    }
+}
+
+impl Display for ByDay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(n) = self.nth {
+            write!(f, "{}", n)?;
+        }
+        f.write_str(&weekday_code(self.weekday))
+    }
+}
+
+impl FromStr for ByDay {
+    type Err = EventError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.len() < 2 || !s.is_char_boundary(s.len() - 2) {
+            return Err(EventError::Parsing);
+        }
+        let (nth, code) = s.split_at(s.len() - 2);
+        let weekday = match code.to_uppercase().as_str() {
+            "MO" => Weekday::Mon,
+            "TU" => Weekday::Tue,
+            "WE" => Weekday::Wed,
+            "TH" => Weekday::Thu,
+            "FR" => Weekday::Fri,
+            "SA" => Weekday::Sat,
+            "SU" => Weekday::Sun,
+            _ => return Err(EventError::Parsing),
+        };
+        let nth = match nth {
+            "" => None,
+            n => match n.trim_start_matches('+').parse::<i32>() {
+                Ok(n) if n != 0 && (-53..=53).contains(&n) => Some(n),
+                _ => return Err(EventError::Parsing),
+            },
+        };
+
+        Ok(ByDay { nth, weekday })
+    }
+}
+
+/// Recurrence rule as described in RFC 5545, supporting the `FREQ`,
+/// `INTERVAL`, `BYDAY`, `COUNT` and `UNTIL` rule parts
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct RRule {
+    pub freq: Periodicity,
+    pub interval: u32,
+    pub by_day: Vec<ByDay>,
+    pub count: Option<u32>,
+    pub until: Option<NaiveDateTime>,
+}
+
+impl RRule {
+    pub fn new(freq: Periodicity) -> Self {
+        RRule {
+            freq,
+            interval: 1,
+            by_day: Vec::new(),
+            count: None,
+            until: None,
+        }
+    }
+
+    /// Get the dates in range `[from, to)` on which an event
+    /// starting at `dtstart` occurs. The start is always the first occurrence.
+    pub fn occurrences(
+        &self,
+        dtstart: NaiveDateTime,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Vec<NaiveDate> {
+        let start = dtstart.date();
+        let mut dates = Vec::new();
+        let mut count = 0;
+
+        for period in 0.. {
+            let period_start = match self.period_start(start, period * self.interval) {
+                Some(date) if date < to => date,
+                _ => break,
+            };
+
+            let mut candidates = self.candidates(start, period_start);
+            if period == 0 && !candidates.contains(&start) {
+                candidates.insert(0, start);
+            }
+
+            for date in candidates.into_iter().filter(|d| *d >= start) {
+                if let Some(until) = self.until {
+                    if NaiveDateTime::new(date, dtstart.time()) > until {
+                        return dates;
+                    }
+                }
+                if let Some(max) = self.count {
+                    if count >= max {
+                        return dates;
+                    }
+                }
+                count += 1;
+
+                if date >= to {
+                    return dates;
+                }
+                if date >= from {
+                    dates.push(date);
+                }
+            }
+        }
+
+        dates
+    }
+
+    /// Rule of a series starting at `dtstart` cut right before the occurrence on `date`
+    pub fn ended_before(&self, dtstart: NaiveDateTime, date: NaiveDate) -> RRule {
+        RRule {
+            count: None,
+            until: Some(NaiveDateTime::new(date, dtstart.time()) - Duration::seconds(1)),
+            ..self.clone()
+        }
+    }
+
+    /// Rule of a series starting at `dtstart` continued from the occurrence on `date`
+    pub fn continued_from(&self, dtstart: NaiveDateTime, date: NaiveDate) -> RRule {
+        let before = self.occurrences(dtstart, dtstart.date(), date).len() as u32;
+        RRule {
+            count: self.count.map(|c| c.saturating_sub(before)),
+            ..self.clone()
+        }
+    }
+
+    /// First day of the `n`-th period after the one containing `start`
+    fn period_start(&self, start: NaiveDate, n: u32) -> Option<NaiveDate> {
+        match self.freq {
+            Periodicity::Daily => start.checked_add_signed(Duration::days(n.into())),
+            Periodicity::Weekly => start
+                .checked_sub_signed(Duration::days(
+                    start.weekday().num_days_from_monday().into(),
+                ))?
+                .checked_add_signed(Duration::weeks(n.into())),
+            Periodicity::Monthly => {
+                let months = start.year() * 12 + start.month0() as i32 + n as i32;
+                NaiveDate::from_ymd_opt(months.div_euclid(12), months.rem_euclid(12) as u32 + 1, 1)
+            }
+            Periodicity::Yearly => NaiveDate::from_ymd_opt(start.year() + n as i32, 1, 1),
+        }
+    }
+
+    /// Sorted dates of the period starting at `period_start` matching the rule
+    fn candidates(&self, start: NaiveDate, period_start: NaiveDate) -> Vec<NaiveDate> {
+        match self.freq {
+            Periodicity::Daily => {
+                if self.by_day.is_empty()
+                    || self
+                        .by_day
+                        .iter()
+                        .any(|d| d.weekday == period_start.weekday())
+                {
+                    vec![period_start]
+                } else {
+                    vec![]
+                }
+            }
+            Periodicity::Weekly => period_start
+                .iter_days()
+                .take(7)
+                .filter(|date| {
+                    if self.by_day.is_empty() {
+                        date.weekday() == start.weekday()
+                    } else {
+                        self.by_day.iter().any(|d| d.weekday == date.weekday())
+                    }
+                })
+                .collect(),
+            Periodicity::Monthly => {
+                if self.by_day.is_empty() {
+                    return NaiveDate::from_ymd_opt(
+                        period_start.year(),
+                        period_start.month(),
+                        start.day(),
+                    )
+                    .into_iter()
+                    .collect();
+                }
+                let days = rayday::get_days_from_month(period_start.year(), period_start.month());
+                period_start
+                    .iter_days()
+                    .take(days as usize)
+                    .filter(|date| {
+                        let position = date.day0() as i32 / 7 + 1;
+                        let from_end = -((days as i32 - date.day() as i32) / 7 + 1);
+                        self.by_day
+                            .iter()
+                            .any(|d| d.matches(*date, position, from_end))
+                    })
+                    .collect()
+            }
+            Periodicity::Yearly => {
+                if self.by_day.is_empty() {
+                    return NaiveDate::from_ymd_opt(
+                        period_start.year(),
+                        start.month(),
+                        start.day(),
+                    )
+                    .into_iter()
+                    .collect();
+                }
+                let days = if rayday::is_leap_year(period_start.year() as u32) {
+                    366
+                } else {
+                    365
+                };
+                period_start
+                    .iter_days()
+                    .take(days)
+                    .filter(|date| {
+                        let position = date.ordinal0() as i32 / 7 + 1;
+                        let from_end = -((days as i32 - date.ordinal() as i32) / 7 + 1);
+                        self.by_day
+                            .iter()
+                            .any(|d| d.matches(*date, position, from_end))
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+impl Display for RRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FREQ={}", self.freq.to_string().to_uppercase())?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let by_day: Vec<String> = self.by_day.iter().map(|d| d.to_string()).collect();
+            write!(f, ";BYDAY={}", by_day.join(","))?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format(PARSE_RRULE_DATETIME))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for RRule {
+    type Err = EventError;
+
+    /// Parse `FREQ=WEEKLY;INTERVAL=2;BYDAY=TU` like rules,
+    /// a plain periodicity like `weekly` is accepted as well
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s.strip_prefix("RRULE:").unwrap_or(s);
+        if !s.contains('=') {
+            return Ok(RRule::new(s.parse::<Periodicity>()?));
+        }
+
+        let mut freq = None;
+        let mut rule = RRule::new(Periodicity::Daily);
+        for part in s.split(';').filter(|p| !p.is_empty()) {
+            let (name, value) = part.split_once('=').ok_or(EventError::Parsing)?;
+            match name.trim().to_uppercase().as_str() {
+                "FREQ" => freq = Some(value.parse::<Periodicity>()?),
+                "INTERVAL" => {
+                    rule.interval = match value.trim().parse::<u32>() {
+                        Ok(i) if i > 0 => i,
+                        _ => return Err(EventError::Parsing),
+                    }
+                }
+                "BYDAY" => {
+                    rule.by_day = value
+                        .split(',')
+                        .map(|d| d.parse::<ByDay>())
+                        .collect::<Result<Vec<ByDay>, EventError>>()?
+                }
+                "COUNT" => {
+                    rule.count = Some(value.trim().parse().map_err(|_| EventError::Parsing)?)
+                }
+                "UNTIL" => rule.until = Some(parse_until(value)?),
+                "WKST" => {}
+                _ => return Err(EventError::Parsing),
+            }
+        }
+
+        rule.freq = freq.ok_or(EventError::Parsing)?;
+        Ok(rule)
+    }
+}
+
+/// Parse `UNTIL` value, a plain date includes the whole day
+fn parse_until(value: &str) -> Result<NaiveDateTime, EventError> {
+    let value = value.trim().trim_end_matches('Z');
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(value, PARSE_RRULE_DATETIME) {
+        return Ok(datetime);
+    }
+    NaiveDate::parse_from_str(value, PARSE_RRULE_DATE)
+        .map(|date| date.and_hms_opt(23, 59, 59).unwrap())
+        .map_err(|_| EventError::Parsing)
+}
+
+/// Format excluded dates as the value of the `EXDATE` property
+pub fn format_exdates(exdates: &[NaiveDate]) -> String {
+    exdates
+        .iter()
+        .map(|d| d.format(PARSE_RRULE_DATE).to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Parse the value of the `EXDATE` property, times are ignored
+pub fn parse_exdates(s: &str) -> Result<Vec<NaiveDate>, EventError> {
+    s.split(',')
+        .map(|d| d.trim())
+        .filter(|d| !d.is_empty())
+        .map(|d| {
+            let date = d.split('T').next().unwrap_or(d);
+            NaiveDate::parse_from_str(date, PARSE_RRULE_DATE).map_err(|_| EventError::Parsing)
+        })
+        .collect()
+}
+
+fn weekday_code(weekday: Weekday) -> String {
+    weekday.to_string()[..2].to_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn dtstart(y: i32, m: u32, d: u32) -> NaiveDateTime {
+        date(y, m, d).and_hms_opt(10, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn rrule_round_trip() {
+        let s = "FREQ=MONTHLY;INTERVAL=2;BYDAY=-1FR,2TU;COUNT=10";
+        let rule = s.parse::<RRule>().unwrap();
+
+        assert_eq!(rule.freq, Periodicity::Monthly);
+        assert_eq!(rule.interval, 2);
+        assert_eq!(
+            rule.by_day,
+            vec![
+                ByDay {
+                    nth: Some(-1),
+                    weekday: Weekday::Fri
+                },
+                ByDay {
+                    nth: Some(2),
+                    weekday: Weekday::Tue
+                }
+            ]
+        );
+        assert_eq!(rule.to_string(), s);
+        assert!("FREQ=HOURLY".parse::<RRule>().is_err());
+        assert!("INTERVAL=2".parse::<RRule>().is_err());
+    }
+
+    #[test]
+    fn every_second_tuesday() {
+        let rule = "RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=TU"
+            .parse::<RRule>()
+            .unwrap();
+
+        assert_eq!(
+            rule.occurrences(dtstart(2026, 1, 6), date(2026, 1, 1), date(2026, 2, 10)),
+            vec![date(2026, 1, 6), date(2026, 1, 20), date(2026, 2, 3)]
+        );
+    }
+
+    #[test]
+    fn last_friday_of_the_month() {
+        let rule = "FREQ=MONTHLY;BYDAY=-1FR".parse::<RRule>().unwrap();
+
+        assert_eq!(
+            rule.occurrences(dtstart(2026, 1, 30), date(2026, 1, 1), date(2026, 4, 1)),
+            vec![date(2026, 1, 30), date(2026, 2, 27), date(2026, 3, 27)]
+        );
+    }
+
+    #[test]
+    fn weekdays_until() {
+        let rule = "FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR;UNTIL=20270301"
+            .parse::<RRule>()
+            .unwrap();
+
+        let dates = rule.occurrences(dtstart(2027, 2, 25), date(2027, 2, 1), date(2027, 4, 1));
+        assert_eq!(
+            dates,
+            vec![date(2027, 2, 25), date(2027, 2, 26), date(2027, 3, 1)]
+        );
+    }
+
+    #[test]
+    fn count_includes_occurrences_before_range() {
+        let rule = "FREQ=DAILY;COUNT=3".parse::<RRule>().unwrap();
+
+        assert_eq!(
+            rule.occurrences(dtstart(2026, 1, 1), date(2026, 1, 2), date(2026, 2, 1)),
+            vec![date(2026, 1, 2), date(2026, 1, 3)]
+        );
+    }
+
+    #[test]
+    fn split_series() {
+        let rule = "FREQ=WEEKLY;COUNT=6".parse::<RRule>().unwrap();
+        let start = dtstart(2026, 1, 5);
+        let split = date(2026, 1, 19);
+
+        let ended = rule.ended_before(start, split);
+        assert_eq!(
+            ended.occurrences(start, date(2026, 1, 1), date(2026, 3, 1)),
+            vec![date(2026, 1, 5), date(2026, 1, 12)]
+        );
+
+        let continued = rule.continued_from(start, split);
+        assert_eq!(continued.count, Some(4));
+        assert_eq!(
+            continued
+                .occurrences(dtstart(2026, 1, 19), date(2026, 1, 1), date(2026, 3, 1))
+                .len(),
+            4
+        );
+    }
+
+    #[test]
+    fn exdates_round_trip() {
+        let dates = vec![date(2026, 1, 6), date(2026, 12, 31)];
+
+        assert_eq!(format_exdates(&dates), "20260106,20261231");
+        assert_eq!(parse_exdates("20260106,20261231T100000").unwrap(), dates);
+        assert!(parse_exdates("").unwrap().is_empty());
+    }
+}
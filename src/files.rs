@@ -1,8 +1,8 @@
 use anyhow::{anyhow, Error as AnyhowError, Result};
 //use config::{Config, ConfigError, Map, Source, Value};
-use confy::{load_path, store_path};
+use confy::store_path;
 use log2::info;
-use rusqlite::{params, Connection, Params};
+use rusqlite::{params, Connection, OptionalExtension, Params, Row};
 use std::{
     collections::HashMap,
     default::Default,
@@ -14,21 +14,70 @@ use std::{
 };
 
 use chrono::{prelude::*, Duration};
+use chrono_tz::Tz;
 use serde_derive::{Deserialize, Serialize};
 use serde_yaml::*;
 use tui::style::Color;
 
-use crate::event::{Event, EventTime, EventTimeError, Today};
+use crate::{
+    event::{
+        format_reminders, format_tags, parse_reminders, parse_tags,
+        rrule::{format_exdates, parse_exdates, RRule},
+        EditScope, Event, EventTime, EventTimeError, Today, DEFAULT_CALENDAR,
+    },
+    store::{EventStore, StorageError, StorageResult, TodoStore, JOURNAL_LENGTH},
+    todo::Todo,
+};
+
+mod backup;
+mod migrations;
+mod paths;
 
-const CONFIG_DIR: &str = ".config";
-const APP_CONFIG_DIR: &str = "rayday";
-const CONFIG_NAME: &str = "config.yml";
-const EVENTS_NAME: &str = "events.db";
+pub use backup::Snapshot;
+pub use paths::Paths;
+
+/// Calendar the events are grouped in, e.g. work, personal or on-call
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Calendar {
+    pub name: String,
+    pub color: Color,
+    /// Whether the events of the calendar are shown until toggled in the app
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+}
+
+fn default_visible() -> bool {
+    true
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct Config {
     pub highlight_color: Color, //TODO tui feature "serde"
     pub event_color: Color,
+    /// Colors of the events by their tags
+    #[serde(default)]
+    pub tag_colors: HashMap<String, Color>,
+    /// Command run with the message of a reminder as its last argument
+    #[serde(default)]
+    pub notify_command: Option<String>,
+    /// Days the deleted events are kept in the trash, 0 keeps them until purged
+    #[serde(default = "default_trash_days")]
+    pub trash_days: u32,
+    /// Calendars of the events, new events go to the first one
+    #[serde(default = "default_calendars")]
+    pub calendars: Vec<Calendar>,
+}
+
+fn default_trash_days() -> u32 {
+    30
+}
+
+fn default_calendars() -> Vec<Calendar> {
+    vec![Calendar {
+        name: String::from(DEFAULT_CALENDAR),
+        color: Color::LightBlue,
+        visible: true,
+    }]
 }
 
 impl Default for Config {
@@ -36,135 +85,1044 @@ impl Default for Config {
         Self {
             highlight_color: Color::LightBlue,
             event_color: Color::LightBlue,
+            tag_colors: HashMap::from([
+                (String::from("meeting"), Color::Yellow),
+                (String::from("focus"), Color::Green),
+                (String::from("personal"), Color::Magenta),
+            ]),
+            notify_command: None,
+            trash_days: default_trash_days(),
+            calendars: default_calendars(),
         }
     }
 }
 
+impl Config {
+    /// Load the config from `path`, writing the default one if there is none
+    pub fn load(path: &Path) -> Result<Config> {
+        if !path.exists() {
+            let config = Config::default();
+            config.save(path)?;
+            return Ok(config);
+        }
+        Config::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parse the config, the errors tell the line and column of the mistake
+    pub fn parse(text: &str) -> Result<Config> {
+        serde_yaml::from_str(text).map_err(|e| match e.location() {
+            // The message leaves out the location at the very start
+            Some(l) if l.line() == 1 && l.column() == 1 => anyhow!("{} at line 1 column 1", e),
+            _ => anyhow!(e),
+        })
+    }
+
+    /// Write the config to `path`
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_yaml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Color of the first tag of the event that has one, or the color
+    /// of its calendar, or the default event color
+    pub fn event_color(&self, event: &Event) -> Color {
+        event
+            .tags()
+            .iter()
+            .find_map(|t| self.tag_colors.get(t))
+            .or(self.calendar(event.calendar()).map(|c| &c.color))
+            .copied()
+            .unwrap_or(self.event_color)
+    }
+
+    pub fn calendar(&self, name: &str) -> Option<&Calendar> {
+        self.calendars.iter().find(|c| c.name == name)
+    }
+
+    /// Fail unless the calendar is in the config
+    pub fn check_calendar(&self, name: &str) -> Result<()> {
+        match self.calendar(name) {
+            Some(_) => Ok(()),
+            None => Err(anyhow!("No calendar {} in the config", name)),
+        }
+    }
+
+    /// Calendar new events go to
+    pub fn default_calendar(&self) -> &str {
+        self.calendars
+            .first()
+            .map(|c| c.name.as_str())
+            .unwrap_or(DEFAULT_CALENDAR)
+    }
+
+    /// Whether the events of the calendar are shown, those
+    /// of the calendars missing in the config are
+    pub fn is_visible(&self, event: &Event) -> bool {
+        self.calendar(event.calendar()).is_none_or(|c| c.visible)
+    }
+
+    /// Show or hide the calendars as they were toggled in the app,
+    /// the calendars missing in the config are left out
+    pub fn apply_visibility(&mut self, visibility: &[(String, bool)]) {
+        for (name, visible) in visibility {
+            if let Some(calendar) = self.calendars.iter_mut().find(|c| c.name == *name) {
+                calendar.visible = *visible;
+            }
+        }
+    }
+
+    /// Show or hide the calendar at `index`, return it if there is one
+    pub fn toggle_calendar(&mut self, index: usize) -> Option<&Calendar> {
+        let calendar = self.calendars.get_mut(index)?;
+        calendar.visible = !calendar.visible;
+        Some(calendar)
+    }
+}
+
 pub struct Files {
-    config_dir: PathBuf,
-    config: Config,
+    paths: Paths,
     db: Connection,
+    /// Number of the change in the journal the edits are recorded under
+    change: Option<usize>,
+    /// Tool the events are saved by
+    source: Option<String>,
 }
 
 impl Files {
-    pub fn new() -> Result<Files> {
-        match dirs::home_dir() {
-            Some(home) => {
-                let path = Path::new(&home);
-                let home_config_dir = path.join(CONFIG_DIR);
-                let app_config_dir = home_config_dir.join(APP_CONFIG_DIR);
-
-                let config_file_path = app_config_dir.join(CONFIG_NAME);
-                let events_file_path = app_config_dir.join(EVENTS_NAME);
-
-                if !home_config_dir.is_dir() {
-                    fs::create_dir(home_config_dir)?;
-                }
-
-                let config: Config;
-                if !app_config_dir.is_dir() {
-                    fs::create_dir(&app_config_dir)?;
-                }
-                if !config_file_path.exists() {
-                    config = Config::default();
-                    fs::File::create(config_file_path)?
-                        .write(serde_yaml::to_string(&config)?.as_bytes());
-                } else {
-                    config = load_path(config_file_path).unwrap();
-                }
-
-                let db_file = PathBuf::from(app_config_dir.join("events.db"));
-
-                let db = Connection::open(db_file.clone()).expect("Could not connect to db");
-                db.execute(
-                    r"create table if not exists events(
-                        id integer primary key,
-                        description text not null,
-                        start datetime not null,
-                        end datetime not null
-                    )",
-                    params![],
-                )?;
-                Ok(Files {
-                    config_dir: app_config_dir,
-                    config,
-                    db, // rocksdb::DB::open_default(events_file_path).unwrap(),
-                })
-            }
-            None => Err(anyhow!("No $HOME directory found for config")),
+    pub fn new(paths: &Paths) -> StorageResult<Files> {
+        let existed = paths.db.exists();
+        let mut db = Connection::open(&paths.db)?;
+        if existed && migrations::user_version(&db)? < migrations::LATEST_VERSION {
+            backup::take(&db, &paths.backups(), "migration")?;
+            backup::rotate(&paths.backups())?;
         }
+        migrations::migrate(&mut db)?;
+        info!("Opened {}", paths.db.display());
+
+        Ok(Files {
+            paths: paths.clone(),
+            db,
+            change: None,
+            source: None,
+        })
     }
 
-    pub fn add_event(&mut self, event: Event) -> Result<(), Error> {
-        self.db.execute(
-            "insert into events (description, start, end) values (?1, ?2, ?3)",
-            params![event.desc(), event.start(), event.end(),],
-        );
+    /// Take a snapshot of the database, `reason` is kept in its name and
+    /// only the last `MAX_SNAPSHOTS` taken for the same reason are kept
+    pub fn backup(&self, reason: &str) -> StorageResult<Snapshot> {
+        let snapshot = backup::take(&self.db, &self.paths.backups(), reason)?;
+        backup::rotate(&self.paths.backups())?;
+        Ok(snapshot)
+    }
+
+    /// Snapshots of the database, the latest first
+    pub fn snapshots(paths: &Paths) -> StorageResult<Vec<Snapshot>> {
+        backup::list(&paths.backups())
+    }
+
+    /// Roll the database back to the snapshot and return the snapshot of the
+    /// state it had before, if any. The database isn't migrated until restored,
+    /// so that corrupt databases or those of newer versions are restored too
+    pub fn restore(paths: &Paths, snapshot: &Snapshot) -> StorageResult<Option<Snapshot>> {
+        let before = if paths.db.exists() {
+            Some(backup::copy(&paths.db, &paths.backups(), "restore")?)
+        } else {
+            None
+        };
+        let mut db = Connection::open(&paths.db)?;
+        backup::restore(&mut db, snapshot)?;
+        migrations::migrate(&mut db)?;
+        // Rotate after restoring not to delete the snapshot being restored
+        backup::rotate(&paths.backups())?;
+
+        info!("Restored {}", snapshot.path.display());
+        Ok(before)
+    }
+
+    /// Record the events as saved by `source`, e.g. tui or cli
+    pub fn with_source(mut self, source: &str) -> Files {
+        self.source = Some(String::from(source));
+        self
+    }
+
+    fn query<P: Params>(&self, sql: &str, params: P) -> StorageResult<Vec<Event>> {
+        let mut stmt = self.db.prepare(sql)?;
+        let events = stmt
+            .query_map(params, event_from_row)?
+            .collect::<rusqlite::Result<Vec<Event>>>()?;
+        Ok(events)
+    }
+}
+
+impl EventStore for Files {
+    fn add_event(&mut self, event: Event) -> StorageResult<usize> {
+        let now = Local::now().naive_local();
+        let event = event.with_timestamps(Some(now), Some(now), self.source.clone());
+        let tx = self.db.transaction()?;
+        let id = insert_event(&tx, None, &event)?;
+        record(
+            &tx,
+            &mut self.change,
+            id,
+            None,
+            Some(&event.clone().with_id(Some(id))),
+        )?;
+        tx.commit()?;
 
         info!("Adding event {}", event);
-        Ok(())
+        Ok(id)
     }
 
-    pub fn update_event(&mut self, event: Event) -> Result<(), Error> {
-        self.db
-            .execute(
-                "update events set start=?1, end=?2, description=?3 where id=?4",
-                params![event.start(), event.end(), event.desc(), event.id()],
-            )
-            .expect("Could not update event from db");
+    fn update_event(&mut self, event: Event) -> StorageResult<()> {
+        let id = event.id().ok_or(StorageError::MissingId)?;
+        let tx = self.db.transaction()?;
+        let before = select_event(&tx, id)?.ok_or(StorageError::NotFound(id))?;
+        let event = event.with_timestamps(
+            before.created_at(),
+            Some(Local::now().naive_local()),
+            self.source.clone(),
+        );
+        tx.execute(
+            "update events set start=?1, end=?2, description=?3, rrule=?4, exdate=?5,
+                master_id=?6, recurrence_id=?7, all_day=?8, timezone=?9, tags=?10,
+                location=?11, notes=?12, url=?13, reminders=?14, deleted_at=?15,
+                created_at=?16, updated_at=?17, updated_by=?18, calendar=?19 where id=?20",
+            params![
+                event.start(),
+                event.end(),
+                event.desc(),
+                event.rrule().map(|r| r.to_string()),
+                format_exdates(event.exdates()),
+                event.master_id(),
+                event.recurrence_id(),
+                event.is_all_day(),
+                event.timezone().map(|z| z.name()),
+                format_tags(event.tags()),
+                event.location(),
+                event.notes(),
+                event.url(),
+                format_reminders(event.reminders()),
+                event.deleted_at(),
+                event.created_at(),
+                event.updated_at(),
+                event.updated_by(),
+                event.calendar(),
+                id
+            ],
+        )?;
+        tx.execute(
+            "insert into history (event_id, event) values (?1, ?2)",
+            params![id, serde_yaml::to_string(&before)?],
+        )?;
+        let history_id = tx.last_insert_rowid();
+        tx.execute("delete from events_search where rowid=?1", params![id])?;
+        index_event(&tx, id, &event)?;
+        let entry = record(&tx, &mut self.change, id, Some(&before), Some(&event))?;
+        tx.execute(
+            "update journal set history_id = ?1 where id = ?2",
+            params![history_id, entry],
+        )?;
+        tx.commit()?;
 
         info!("Updating event {}", event);
         Ok(())
     }
 
-    pub fn remove_event(&mut self, id: usize) -> Result<(), Error> {
-        let num_of_affected = self
-            .db
-            .execute("delete from events where id=?1", params![id])
-            .expect("Could not remove event from db");
+    fn trash_event(&mut self, id: usize, deleted_at: NaiveDateTime) -> StorageResult<()> {
+        let tx = self.db.transaction()?;
+        if let Some(before) = select_event(&tx, id)? {
+            let after = before
+                .clone()
+                .with_deleted_at(Some(deleted_at))
+                .with_timestamps(before.created_at(), Some(deleted_at), self.source.clone());
+            tx.execute(
+                "update events set deleted_at=?1, updated_at=?2, updated_by=?3 where id=?4",
+                params![
+                    after.deleted_at(),
+                    after.updated_at(),
+                    after.updated_by(),
+                    id
+                ],
+            )?;
+            record(&tx, &mut self.change, id, Some(&before), Some(&after))?;
+        }
+        tx.commit()?;
 
-        info!("Removed {} event with id: {}", num_of_affected, id);
+        info!("Moved event with id {} to the trash", id);
         Ok(())
     }
 
-    pub fn get_event(&self, id: usize) -> Option<Event> {
+    fn purge_event(&mut self, id: usize) -> StorageResult<()> {
+        let tx = self.db.transaction()?;
+        if let Some(before) = select_event(&tx, id)? {
+            delete_event(&tx, id)?;
+            record(&tx, &mut self.change, id, Some(&before), None)?;
+        }
+        tx.commit()?;
+
+        info!("Purged event with id: {}", id);
+        Ok(())
+    }
+
+    fn get_event(&self, id: usize) -> StorageResult<Option<Event>> {
+        Ok(self
+            .query(
+                "select * from events where id = ?1 and deleted_at is null",
+                params![id],
+            )?
+            .pop())
+    }
+
+    fn get_trash(&self) -> StorageResult<Vec<Event>> {
+        self.query(
+            "select * from events where deleted_at is not null order by deleted_at desc",
+            params![],
+        )
+    }
+
+    fn get_history(&self, id: usize) -> StorageResult<Vec<Event>> {
         let mut stmt = self
             .db
-            .prepare("select * from events where id = ?1")
-            .expect("Could not prepare statement");
-        let event = stmt.query([id]).expect("Could not query statement");
+            .prepare("select event from history where event_id = ?1 order by id desc")?;
+        let versions = stmt
+            .query_map(params![id], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        let versions = versions
+            .iter()
+            .map(|v| serde_yaml::from_str(v))
+            .collect::<serde_yaml::Result<Vec<Event>>>()?;
+        Ok(versions)
+    }
 
-        None
+    fn query_events(&self, from: NaiveDateTime, to: NaiveDateTime) -> StorageResult<Vec<Event>> {
+        self.query(
+            "select * from events
+                where deleted_at is null
+                and ((start < ?2 and end >= ?1) or (rrule is not null and start < ?2))
+                order by start asc",
+            params![from, to],
+        )
     }
 
-    pub fn get_events_on_date(&self, date: NaiveDate) -> Vec<Event> {
-        // Get EventTime as keys from db
-        let mut stmt = self
+    fn get_overrides(&self, master_id: usize) -> StorageResult<Vec<Event>> {
+        self.query(
+            "select * from events where master_id = ?1 and deleted_at is null
+                order by recurrence_id asc",
+            params![master_id],
+        )
+    }
+
+    fn search_events(&self, query: &str) -> StorageResult<Vec<Event>> {
+        let query = match_query(query);
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        // Matches in the description weigh more than in the notes
+        self.query(
+            "select events.* from events_search
+                join events on events.id = events_search.rowid
+                where events_search match ?1 and events.deleted_at is null
+                order by bm25(events_search, 2.0, 1.0), events.start desc",
+            params![query],
+        )
+    }
+
+    fn data_version(&self) -> StorageResult<u64> {
+        // `data_version` counts the commits of the other connections,
+        // `total_changes()` the rows changed by this one
+        let others: u64 = self
             .db
-            .prepare("select * from events where start > ?1 and end < ?2 order by start asc")
-            .expect("Could not prepare statement");
+            .query_row("pragma data_version", [], |row| row.get(0))?;
+        let own: u64 = self
+            .db
+            .query_row("select total_changes()", [], |row| row.get(0))?;
+        Ok(others + own)
+    }
+
+    fn end_change(&mut self) {
+        self.change = None;
+    }
+
+    fn get_calendar_visibility(&self) -> StorageResult<Vec<(String, bool)>> {
+        let mut stmt = self.db.prepare("select name, visible from calendars")?;
+        let calendars = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<(String, bool)>>>()?;
+        Ok(calendars)
+    }
 
-        // Query rows and parse Events
-        let event_iter = stmt
-            .query_map([date, date.add(Duration::days(1))], |row| {
-                let id = Some(row.get::<usize, usize>(0).unwrap());
-                Ok(Event::new(
-                    id,
-                    row.get(1).unwrap(),
-                    row.get(2).unwrap(),
-                    row.get(3).unwrap(),
-                ))
-            })
-            .expect("Could not query rows");
+    fn set_calendar_visible(&mut self, name: &str, visible: bool) -> StorageResult<()> {
+        self.db.execute(
+            "insert into calendars (name, visible) values (?1, ?2)
+                on conflict(name) do update set visible = ?2",
+            params![name, visible],
+        )?;
+        info!("Calendar {} visible: {}", name, visible);
+        Ok(())
+    }
+
+    fn undo(&mut self) -> StorageResult<bool> {
+        self.change = None;
+        let tx = self.db.transaction()?;
+        let change: Option<usize> = tx.query_row(
+            "select max(change) from journal where undone = 0",
+            [],
+            |row| row.get(0),
+        )?;
+        let change = match change {
+            Some(change) => change,
+            None => return Ok(false),
+        };
+
+        // Revert the events in the reverse order of the changes
+        let entries = journal_entries(&tx, change, "before", "desc")?;
+        for (id, before) in entries {
+            restore_event(&tx, id, before.as_deref())?;
+        }
+        // The versions replaced by the undone updates leave the history
+        tx.execute(
+            "delete from history where id in
+                (select history_id from journal where change = ?1)",
+            params![change],
+        )?;
+        tx.execute(
+            "update journal set undone = 1 where change = ?1",
+            params![change],
+        )?;
+        tx.commit()?;
 
-        // Collect events
-        event_iter.map(|e| e.unwrap()).collect()
+        info!("Undid change {}", change);
+        Ok(true)
     }
 
-    pub fn get_config(&self) -> &Config {
-        &self.config
+    fn redo(&mut self) -> StorageResult<bool> {
+        self.change = None;
+        let tx = self.db.transaction()?;
+        let change: Option<usize> = tx.query_row(
+            "select min(change) from journal where undone = 1",
+            [],
+            |row| row.get(0),
+        )?;
+        let change = match change {
+            Some(change) => change,
+            None => return Ok(false),
+        };
+
+        let entries = journal_entries(&tx, change, "after", "asc")?;
+        for (id, after) in entries {
+            restore_event(&tx, id, after.as_deref())?;
+        }
+        // and come back to it when redone
+        for (entry, id, before) in replaced_versions(&tx, change)? {
+            tx.execute(
+                "insert into history (event_id, event) values (?1, ?2)",
+                params![id, before],
+            )?;
+            tx.execute(
+                "update journal set history_id = ?1 where id = ?2",
+                params![tx.last_insert_rowid(), entry],
+            )?;
+        }
+        tx.execute(
+            "update journal set undone = 0 where change = ?1",
+            params![change],
+        )?;
+        tx.commit()?;
+
+        info!("Redid change {}", change);
+        Ok(true)
     }
 }
 
+/// Insert the event with `id`, or a new id if there is none, and index it
+fn insert_event(db: &Connection, id: Option<usize>, event: &Event) -> rusqlite::Result<usize> {
+    db.execute(
+        "insert into events
+            (id, description, start, end, rrule, exdate, master_id, recurrence_id, all_day,
+                timezone, tags, location, notes, url, reminders, deleted_at, created_at,
+                updated_at, updated_by, calendar)
+            values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16,
+                ?17, ?18, ?19, ?20)",
+        params![
+            id,
+            event.desc(),
+            event.start(),
+            event.end(),
+            event.rrule().map(|r| r.to_string()),
+            format_exdates(event.exdates()),
+            event.master_id(),
+            event.recurrence_id(),
+            event.is_all_day(),
+            event.timezone().map(|z| z.name()),
+            format_tags(event.tags()),
+            event.location(),
+            event.notes(),
+            event.url(),
+            format_reminders(event.reminders()),
+            event.deleted_at(),
+            event.created_at(),
+            event.updated_at(),
+            event.updated_by(),
+            event.calendar(),
+        ],
+    )?;
+    let id = db.last_insert_rowid() as usize;
+    index_event(db, id, event)?;
+    Ok(id)
+}
+
+fn select_event(db: &Connection, id: usize) -> rusqlite::Result<Option<Event>> {
+    db.query_row(
+        "select * from events where id = ?1",
+        params![id],
+        event_from_row,
+    )
+    .optional()
+}
+
+/// Delete the event along with its history, its id may be given to another event
+fn delete_event(db: &Connection, id: usize) -> rusqlite::Result<()> {
+    remove_row(db, id)?;
+    db.execute("delete from history where event_id=?1", params![id])?;
+    Ok(())
+}
+
+fn remove_row(db: &Connection, id: usize) -> rusqlite::Result<()> {
+    db.execute("delete from events where id=?1", params![id])?;
+    db.execute("delete from events_search where rowid=?1", params![id])?;
+    Ok(())
+}
+
+/// Put the event saved in the journal back in place of the event with `id`
+fn restore_event(db: &Connection, id: usize, event: Option<&str>) -> StorageResult<()> {
+    match event {
+        Some(event) => {
+            let event: Event = serde_yaml::from_str(event)?;
+            remove_row(db, id)?;
+            insert_event(db, Some(id), &event)?;
+        }
+        None => delete_event(db, id)?,
+    }
+    Ok(())
+}
+
+/// Ids of the events changed by `change` with their `column` version
+fn journal_entries(
+    db: &Connection,
+    change: usize,
+    column: &str,
+    order: &str,
+) -> rusqlite::Result<Vec<(usize, Option<String>)>> {
+    let mut stmt = db.prepare(&format!(
+        "select event_id, {} from journal where change = ?1 order by id {}",
+        column, order
+    ))?;
+    let entries = stmt
+        .query_map(params![change], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect();
+    entries
+}
+
+/// Journal entries of the updates of `change` which kept
+/// the version they replaced in the history, with that version
+fn replaced_versions(
+    db: &Connection,
+    change: usize,
+) -> rusqlite::Result<Vec<(i64, usize, String)>> {
+    let mut stmt = db.prepare(
+        "select id, event_id, before from journal
+            where change = ?1 and history_id is not null order by id",
+    )?;
+    let entries = stmt
+        .query_map(params![change], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?
+        .collect();
+    entries
+}
+
+/// Record the change of an event in the journal as part of the `change` being made,
+/// starting a new one if there is none and forgetting the undone changes,
+/// return the id of the entry
+fn record(
+    db: &Connection,
+    change: &mut Option<usize>,
+    id: usize,
+    before: Option<&Event>,
+    after: Option<&Event>,
+) -> StorageResult<i64> {
+    let current = match *change {
+        Some(current) => current,
+        None => {
+            db.execute("delete from journal where undone = 1", params![])?;
+            let last: Option<usize> =
+                db.query_row("select max(change) from journal", [], |row| row.get(0))?;
+            let current = last.unwrap_or_default() + 1;
+            db.execute(
+                "delete from journal where change <= ?1",
+                params![current.saturating_sub(JOURNAL_LENGTH)],
+            )?;
+            *change = Some(current);
+            current
+        }
+    };
+
+    let before = before.map(serde_yaml::to_string).transpose()?;
+    let after = after.map(serde_yaml::to_string).transpose()?;
+    db.execute(
+        "insert into journal (change, event_id, before, after) values (?1, ?2, ?3, ?4)",
+        params![current, id, before, after],
+    )?;
+    Ok(db.last_insert_rowid())
+}
+
+/// Add the event to the full-text index
+fn index_event(db: &Connection, id: usize, event: &Event) -> rusqlite::Result<()> {
+    db.execute(
+        "insert into events_search (rowid, description, notes) values (?1, ?2, ?3)",
+        params![id, event.desc(), event.notes()],
+    )?;
+    Ok(())
+}
+
+/// Turn the words typed by the user into an FTS5 query matching all of them as prefixes
+fn match_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|w| format!("\"{}\"*", w.replace('"', "\"\"")))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+impl TodoStore for Files {
+    fn add_todo(&mut self, todo: Todo) -> StorageResult<usize> {
+        self.db.execute(
+            "insert into todos (description, priority, due, completed) values (?1, ?2, ?3, ?4)",
+            params![
+                todo.desc(),
+                todo.priority(),
+                todo.due(),
+                todo.is_completed()
+            ],
+        )?;
+
+        info!("Adding todo {}", todo);
+        Ok(self.db.last_insert_rowid() as usize)
+    }
+
+    fn update_todo(&mut self, todo: Todo) -> StorageResult<()> {
+        let id = todo.id().ok_or(StorageError::MissingId)?;
+        let num_of_affected = self.db.execute(
+            "update todos set description=?1, priority=?2, due=?3, completed=?4 where id=?5",
+            params![
+                todo.desc(),
+                todo.priority(),
+                todo.due(),
+                todo.is_completed(),
+                id
+            ],
+        )?;
+        if num_of_affected == 0 {
+            return Err(StorageError::NotFound(id));
+        }
+
+        info!("Updating todo {}", todo);
+        Ok(())
+    }
+
+    fn remove_todo(&mut self, id: usize) -> StorageResult<()> {
+        let num_of_affected = self
+            .db
+            .execute("delete from todos where id=?1", params![id])?;
+
+        info!("Removed {} todo with id: {}", num_of_affected, id);
+        Ok(())
+    }
+
+    fn get_todos(&self) -> StorageResult<Vec<Todo>> {
+        let mut stmt = self.db.prepare(
+            "select * from todos
+                order by completed asc, priority asc, due is null, due asc, id asc",
+        )?;
+
+        let todos = stmt
+            .query_map([], todo_from_row)?
+            .collect::<rusqlite::Result<Vec<Todo>>>()?;
+        Ok(todos)
+    }
+}
+
+/// Parse an event from a row of the `events` table
+pub(crate) fn event_from_row(row: &Row) -> rusqlite::Result<Event> {
+    let rrule: Option<String> = row.get("rrule")?;
+    let exdate: Option<String> = row.get("exdate")?;
+    let timezone: Option<String> = row.get("timezone")?;
+    let tags: Option<String> = row.get("tags")?;
+    let reminders: Option<String> = row.get("reminders")?;
+
+    Ok(Event::new(
+        Some(row.get("id")?),
+        row.get("description")?,
+        row.get("start")?,
+        row.get("end")?,
+    )
+    .with_rrule(rrule.and_then(|r| r.parse::<RRule>().ok()))
+    .with_master(row.get("master_id")?, row.get("recurrence_id")?)
+    .with_all_day(row.get("all_day")?)
+    .with_timezone(timezone.and_then(|z| z.parse::<Tz>().ok()))
+    .with_tags(tags.map(|t| parse_tags(&t)).unwrap_or_default())
+    .with_location(row.get("location")?)
+    .with_notes(row.get("notes")?)
+    .with_url(row.get("url")?)
+    .with_calendar(row.get("calendar")?)
+    .with_deleted_at(row.get("deleted_at")?)
+    .with_timestamps(
+        row.get("created_at")?,
+        row.get("updated_at")?,
+        row.get("updated_by")?,
+    )
+    .with_reminders(
+        reminders
+            .and_then(|r| parse_reminders(&r).ok())
+            .unwrap_or_default(),
+    )
+    .with_exdates(
+        exdate
+            .and_then(|e| parse_exdates(&e).ok())
+            .unwrap_or_default(),
+    ))
+}
+
+/// Parse a todo from a row of the `todos` table
+fn todo_from_row(row: &Row) -> rusqlite::Result<Todo> {
+    Ok(Todo::new(
+        Some(row.get("id")?),
+        row.get("description")?,
+        row.get("priority")?,
+    )
+    .with_due(row.get("due")?)
+    .with_completed(row.get("completed")?))
+}
+
 #[cfg(test)]
-mod tests {}
+pub(crate) mod tests {
+    use super::*;
+
+    fn in_memory() -> Files {
+        let mut db = Connection::open_in_memory().unwrap();
+        migrations::migrate(&mut db).unwrap();
+        Files {
+            paths: Paths {
+                config: PathBuf::new(),
+                db: PathBuf::new(),
+                log: PathBuf::new(),
+            },
+            db,
+            change: None,
+            source: None,
+        }
+    }
+
+    /// Paths in a temporary directory, removed when dropped even if the test fails
+    pub(crate) struct TempPaths(Paths);
+
+    impl std::ops::Deref for TempPaths {
+        type Target = Paths;
+
+        fn deref(&self) -> &Paths {
+            &self.0
+        }
+    }
+
+    impl Drop for TempPaths {
+        fn drop(&mut self) {
+            if let Some(dir) = self.0.db.parent() {
+                let _ = fs::remove_dir_all(dir);
+            }
+        }
+    }
+
+    /// Paths of the files of the test `name` in their own temporary directory
+    pub(crate) fn temp_paths(name: &str) -> TempPaths {
+        let dir = std::env::temp_dir().join(format!("rayday-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        TempPaths(Paths {
+            config: dir.join("config.yml"),
+            db: dir.join("events.db"),
+            log: dir.join("log.txt"),
+        })
+    }
+
+    /// Start of the events of the tests
+    fn start() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 7, 18)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn calendars_color_and_hide_events() {
+        let mut files = in_memory();
+        let start = start();
+        let event = Event::new(None, String::from("Pager"), start, start)
+            .with_calendar(String::from("on-call"));
+        let id = files.add_event(event).unwrap();
+        let stored = files.get_event(id).unwrap().unwrap();
+        assert_eq!(stored.calendar(), "on-call");
+
+        let mut config = Config::default();
+        assert!(config.is_visible(&stored));
+        assert!(config.check_calendar("on-call").is_err());
+        config.calendars.push(Calendar {
+            name: String::from("on-call"),
+            color: Color::Red,
+            visible: true,
+        });
+        assert_eq!(config.event_color(&stored), Color::Red);
+        assert!(config.check_calendar("on-call").is_ok());
+        config.toggle_calendar(1);
+        assert!(!config.is_visible(&stored));
+
+        // Toggled calendars are kept in the database, not in the config
+        files.set_calendar_visible("on-call", true).unwrap();
+        config.apply_visibility(&files.get_calendar_visibility().unwrap());
+        assert!(config.is_visible(&stored));
+    }
+
+    #[test]
+    fn get_event_by_id() {
+        let mut files = in_memory();
+        let start = start();
+        let event = Event::new(
+            None,
+            String::from("Review"),
+            start,
+            start + Duration::hours(1),
+        )
+        .with_location(Some(String::from("Room 2")));
+        let id = files.add_event(event.clone()).unwrap();
+        files.add_event(event.clone()).unwrap();
+
+        let stored = files.get_event(id).unwrap().unwrap();
+        assert!(stored.created_at().is_some());
+        assert_eq!(
+            stored.with_timestamps(None, None, None),
+            event.with_id(Some(id))
+        );
+        assert_eq!(files.get_event(id + 2).unwrap(), None);
+    }
+
+    #[test]
+    fn search_is_kept_up_to_date() {
+        let mut files = in_memory();
+        let start = start();
+        let dentist = Event::new(
+            None,
+            String::from("Dentist"),
+            start,
+            start + Duration::hours(1),
+        );
+        let checkup = Event::new(
+            None,
+            String::from("Checkup"),
+            start + Duration::weeks(4),
+            start + Duration::weeks(4) + Duration::hours(1),
+        )
+        .with_notes(Some(String::from("Same dentist, bring the X-ray")));
+        let first = files.add_event(dentist).unwrap();
+        let second = files.add_event(checkup).unwrap();
+
+        let ids = |files: &Files, query| -> Vec<Option<usize>> {
+            files
+                .search_events(query)
+                .unwrap()
+                .iter()
+                .map(|e| e.id())
+                .collect()
+        };
+        assert_eq!(ids(&files, "dent"), vec![Some(first), Some(second)]);
+        assert_eq!(ids(&files, "dentist x-ray\""), vec![Some(second)]);
+
+        let event = files.get_event(first).unwrap().unwrap();
+        files
+            .update_event(Event::new(
+                event.id(),
+                String::from("Haircut"),
+                event.start(),
+                event.end(),
+            ))
+            .unwrap();
+        assert_eq!(ids(&files, "dentist"), vec![Some(second)]);
+        assert_eq!(ids(&files, "hair"), vec![Some(first)]);
+
+        files.remove_event(second).unwrap();
+        assert!(ids(&files, "dentist").is_empty());
+        assert!(ids(&files, " ").is_empty());
+    }
+
+    #[test]
+    fn data_version_follows_other_connections() {
+        let paths = temp_paths("version");
+        let start = start();
+
+        let mut files = Files::new(&paths).unwrap();
+        let opened = files.data_version().unwrap();
+        assert_eq!(files.data_version().unwrap(), opened);
+        files
+            .add_event(Event::new(None, String::from("Dentist"), start, start))
+            .unwrap();
+        let version = files.data_version().unwrap();
+        assert_ne!(version, opened);
+
+        // Edits of the sqlite3 shell or another rayday are seen too
+        let other = Connection::open(&paths.db).unwrap();
+        other
+            .execute("update events set description = 'Haircut'", params![])
+            .unwrap();
+        assert_ne!(files.data_version().unwrap(), version);
+    }
+
+    #[test]
+    fn databases_of_newer_versions_are_restored() {
+        let paths = temp_paths("restore");
+        let snapshot = Files::new(&paths).unwrap().backup("manual").unwrap();
+        Connection::open(&paths.db)
+            .unwrap()
+            .pragma_update(None, "user_version", migrations::LATEST_VERSION + 1)
+            .unwrap();
+        assert!(Files::new(&paths).is_err());
+
+        let before = Files::restore(&paths, &snapshot).unwrap().unwrap();
+        assert!(Files::new(&paths).is_ok());
+        assert_eq!(Files::snapshots(&paths).unwrap()[0], before);
+    }
+
+    #[test]
+    fn undo_survives_restarts() {
+        let paths = temp_paths("undo");
+        let start = start();
+
+        let mut files = Files::new(&paths).unwrap();
+        let id = files
+            .add_event(Event::new(
+                None,
+                String::from("Dentist"),
+                start,
+                start + Duration::hours(1),
+            ))
+            .unwrap();
+        files.end_change();
+        files.remove_event(id).unwrap();
+        drop(files);
+
+        let mut files = Files::new(&paths).unwrap();
+        assert!(files.undo().unwrap());
+        assert_eq!(files.get_event(id).unwrap().unwrap().desc(), "Dentist");
+        assert_eq!(files.search_events("dentist").unwrap().len(), 1);
+        assert!(files.undo().unwrap());
+        assert_eq!(files.get_event(id).unwrap(), None);
+        assert!(!files.undo().unwrap());
+
+        assert!(files.redo().unwrap());
+        assert!(files.get_event(id).unwrap().is_some());
+        // A new change drops the changes left to redo
+        files.end_change();
+        files
+            .update_event(Event::new(Some(id), String::from("Haircut"), start, start))
+            .unwrap();
+        assert!(!files.redo().unwrap());
+        assert!(files.undo().unwrap());
+        assert_eq!(files.get_event(id).unwrap().unwrap().desc(), "Dentist");
+    }
+
+    #[test]
+    fn removed_events_go_to_the_trash() {
+        let mut files = in_memory();
+        let start = start();
+        let id = files
+            .add_event(Event::new(
+                None,
+                String::from("Dentist"),
+                start,
+                start + Duration::hours(1),
+            ))
+            .unwrap();
+
+        files.remove_event(id).unwrap();
+        assert_eq!(files.get_event(id).unwrap(), None);
+        let trashed = &files.get_trash().unwrap()[0];
+        assert_eq!(trashed.updated_at(), trashed.deleted_at());
+        assert!(files.get_events_on_date(start.date()).unwrap().is_empty());
+        assert!(files.search_events("dentist").unwrap().is_empty());
+        assert_eq!(files.get_trash().unwrap()[0].id(), Some(id));
+
+        files.restore_event(id).unwrap();
+        assert_eq!(files.get_events_on_date(start.date()).unwrap().len(), 1);
+        // Restoring updates the event and keeps the deleted version
+        let history = files.get_history(id).unwrap();
+        assert_eq!(history.len(), 1);
+        assert!(history[0].deleted_at().is_some());
+
+        files.remove_event(id).unwrap();
+        files.end_change();
+        files.purge_from_trash(id).unwrap();
+        assert!(files.get_trash().unwrap().is_empty());
+        // Purging is a change like the others
+        assert!(files.undo().unwrap());
+        assert_eq!(files.get_trash().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn undone_updates_leave_the_history() {
+        let mut files = in_memory();
+        let start = start();
+        let event = Event::new(None, String::from("Dentist"), start, start);
+        let id = files.add_event(event.clone()).unwrap();
+        files.end_change();
+        files
+            .update_event(
+                event
+                    .with_id(Some(id))
+                    .with_location(Some(String::from("Room 1"))),
+            )
+            .unwrap();
+        assert_eq!(files.get_history(id).unwrap().len(), 1);
+
+        assert!(files.undo().unwrap());
+        assert!(files.get_history(id).unwrap().is_empty());
+        assert!(files.redo().unwrap());
+        let history = files.get_history(id).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].location(), None);
+    }
+
+    #[test]
+    fn undone_events_leave_no_history_to_their_id() {
+        let mut files = in_memory();
+        let start = start();
+        let event = Event::new(
+            None,
+            String::from("Dentist"),
+            start,
+            start + Duration::hours(1),
+        );
+        let id = files.add_event(event.clone()).unwrap();
+        files.end_change();
+        files
+            .update_event(
+                event
+                    .clone()
+                    .with_id(Some(id))
+                    .with_location(Some(String::from("Room 1"))),
+            )
+            .unwrap();
+        files.end_change();
+        assert!(files.undo().unwrap());
+        assert!(files.undo().unwrap());
+
+        // The id is free again and goes to the next event
+        assert_eq!(files.add_event(event).unwrap(), id);
+        assert!(files.get_history(id).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,167 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{Duration, Local, NaiveDateTime, Timelike};
+use rusqlite::{Connection, DatabaseName};
+
+use crate::store::StorageResult;
+
+/// Number of the last snapshots kept for each reason, the older ones are deleted
+pub const MAX_SNAPSHOTS: usize = 10;
+
+const PREFIX: &str = "events-";
+const EXTENSION: &str = "db";
+const TIME_FORMAT: &str = "%Y%m%d-%H%M%S%.3f";
+
+/// Copy of the database taken at some point
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub path: PathBuf,
+    pub taken_at: NaiveDateTime,
+    /// Why the snapshot was taken, e.g. startup or migration
+    pub reason: String,
+}
+
+impl Snapshot {
+    /// Parse the snapshot from its file name, `events-<time>-<reason>.db`
+    fn from_path(path: PathBuf) -> Option<Snapshot> {
+        if path.extension()? != EXTENSION {
+            return None;
+        }
+        let name = path.file_stem()?.to_str()?.strip_prefix(PREFIX)?;
+        // The time has a fixed length of 19 characters
+        let (time, reason) = (name.get(..19)?, name.get(20..)?);
+        let taken_at = NaiveDateTime::parse_from_str(time, TIME_FORMAT).ok()?;
+        Some(Snapshot {
+            reason: String::from(reason),
+            taken_at,
+            path,
+        })
+    }
+}
+
+/// Copy the database into `dir` with the online backup API
+pub fn take(db: &Connection, dir: &Path, reason: &str) -> StorageResult<Snapshot> {
+    let snapshot = next_snapshot(dir, reason)?;
+    db.backup(DatabaseName::Main, &snapshot.path, None)?;
+    Ok(snapshot)
+}
+
+/// Copy the file of the database into `dir` as it is, even if it can't be opened
+pub fn copy(db: &Path, dir: &Path, reason: &str) -> StorageResult<Snapshot> {
+    let snapshot = next_snapshot(dir, reason)?;
+    fs::copy(db, &snapshot.path)?;
+    Ok(snapshot)
+}
+
+/// Name the next snapshot taken into `dir`
+fn next_snapshot(dir: &Path, reason: &str) -> StorageResult<Snapshot> {
+    fs::create_dir_all(dir)?;
+    // Keep the times in the order the snapshots are taken
+    let now = Local::now().naive_local();
+    let mut taken_at = now
+        .with_nanosecond(now.nanosecond() / 1_000_000 * 1_000_000)
+        .unwrap();
+    if let Some(latest) = list(dir)?.first() {
+        taken_at = taken_at.max(latest.taken_at + Duration::milliseconds(1));
+    }
+    let path = dir.join(format!(
+        "{}{}-{}.{}",
+        PREFIX,
+        taken_at.format(TIME_FORMAT),
+        reason,
+        EXTENSION
+    ));
+    Ok(Snapshot {
+        path,
+        taken_at,
+        reason: String::from(reason),
+    })
+}
+
+/// Delete the snapshots older than the last `MAX_SNAPSHOTS` taken for the same reason
+pub fn rotate(dir: &Path) -> StorageResult<()> {
+    // Snapshots of every launch don't push out the manual ones
+    let mut kept = HashMap::new();
+    for snapshot in list(dir)? {
+        let count = kept.entry(snapshot.reason.clone()).or_insert(0);
+        *count += 1;
+        if *count > MAX_SNAPSHOTS {
+            fs::remove_file(&snapshot.path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Snapshots in `dir`, the latest first
+pub fn list(dir: &Path) -> StorageResult<Vec<Snapshot>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        if let Some(snapshot) = Snapshot::from_path(entry?.path()) {
+            snapshots.push(snapshot);
+        }
+    }
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.taken_at));
+    Ok(snapshots)
+}
+
+/// Replace the contents of the database with the snapshot
+pub fn restore(db: &mut Connection, snapshot: &Snapshot) -> StorageResult<()> {
+    db.restore(
+        DatabaseName::Main,
+        &snapshot.path,
+        None::<fn(rusqlite::backup::Progress)>,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::files::tests::temp_paths;
+
+    #[test]
+    fn snapshots_rotate_and_restore() {
+        let paths = temp_paths("backup");
+        let dir = paths.backups();
+        let mut db = Connection::open_in_memory().unwrap();
+        db.execute_batch("create table t(x integer); insert into t values (1);")
+            .unwrap();
+
+        let manual = take(&db, &dir, "manual").unwrap();
+        let first = take(&db, &dir, "startup").unwrap();
+        assert_eq!(list(&dir).unwrap(), vec![first.clone(), manual.clone()]);
+
+        db.execute("insert into t values (2)", []).unwrap();
+        restore(&mut db, &first).unwrap();
+        let count: usize = db
+            .query_row("select count(*) from t", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+
+        for _ in 0..MAX_SNAPSHOTS {
+            take(&db, &dir, "startup").unwrap();
+            rotate(&dir).unwrap();
+        }
+        // Only the oldest startup snapshot goes
+        let snapshots = list(&dir).unwrap();
+        assert_eq!(snapshots.len(), MAX_SNAPSHOTS + 1);
+        assert!(!snapshots.contains(&first));
+        assert!(snapshots.contains(&manual));
+
+        // Manual snapshots are limited the same way
+        for _ in 0..MAX_SNAPSHOTS {
+            take(&db, &dir, "manual").unwrap();
+            rotate(&dir).unwrap();
+        }
+        let snapshots = list(&dir).unwrap();
+        assert_eq!(snapshots.len(), 2 * MAX_SNAPSHOTS);
+        assert!(!snapshots.contains(&manual));
+    }
+}
@@ -0,0 +1,346 @@
+use log2::info;
+use rusqlite::{params, Connection};
+
+use crate::{
+    event::DEFAULT_CALENDAR,
+    store::{StorageError, StorageResult},
+};
+
+type Migration = fn(&Connection) -> rusqlite::Result<()>;
+
+/// Migrations of the database schema, the one at index `i`
+/// upgrades the database from version `i` to version `i + 1`
+const MIGRATIONS: &[Migration] = &[
+    create_events,
+    add_recurrence,
+    add_all_day,
+    add_timezone,
+    create_todos,
+    add_tags,
+    add_details,
+    add_reminders,
+    add_indexes,
+    create_search,
+    create_journal,
+    add_trash,
+    add_history,
+    add_calendars,
+    create_calendars,
+    add_journal_history,
+];
+
+pub const LATEST_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Version of the database schema stored in `PRAGMA user_version`
+pub fn user_version(db: &Connection) -> rusqlite::Result<u32> {
+    db.query_row("pragma user_version", [], |row| row.get(0))
+}
+
+/// Upgrade the database to the latest version
+pub fn migrate(db: &mut Connection) -> StorageResult<()> {
+    migrate_to(db, LATEST_VERSION)
+}
+
+/// Upgrade the database step by step to `version`,
+/// each step is applied in its own transaction
+pub fn migrate_to(db: &mut Connection, version: u32) -> StorageResult<()> {
+    let current = user_version(db)?;
+    if current > LATEST_VERSION {
+        return Err(StorageError::UnsupportedVersion {
+            found: current,
+            supported: LATEST_VERSION,
+        });
+    }
+
+    for (from, migration) in MIGRATIONS
+        .iter()
+        .enumerate()
+        .take(version as usize)
+        .skip(current as usize)
+    {
+        let tx = db.transaction()?;
+        migration(&tx)?;
+        tx.pragma_update(None, "user_version", from as u32 + 1)?;
+        tx.commit()?;
+        info!("Migrated database from version {} to {}", from, from + 1);
+    }
+    Ok(())
+}
+
+fn has_column(db: &Connection, table: &str, column: &str) -> rusqlite::Result<bool> {
+    let mut stmt = db.prepare(&format!("pragma table_info({})", table))?;
+    let mut names = stmt.query_map([], |row| row.get::<_, String>("name"))?;
+    Ok(names.any(|name| name.is_ok_and(|n| n == column)))
+}
+
+/// Add a column to the table unless it is there already,
+/// databases from before the versioning have no version but may have some columns
+fn add_column(
+    db: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> rusqlite::Result<()> {
+    if !has_column(db, table, column)? {
+        db.execute(
+            &format!("alter table {} add column {} {}", table, column, definition),
+            params![],
+        )?;
+    }
+    Ok(())
+}
+
+fn create_events(db: &Connection) -> rusqlite::Result<()> {
+    db.execute(
+        r"create table if not exists events(
+            id integer primary key,
+            description text not null,
+            start datetime not null,
+            end datetime not null
+        )",
+        params![],
+    )?;
+    Ok(())
+}
+
+fn add_recurrence(db: &Connection) -> rusqlite::Result<()> {
+    add_column(db, "events", "rrule", "text")?;
+    add_column(db, "events", "exdate", "text")?;
+    add_column(db, "events", "master_id", "integer references events(id)")?;
+    add_column(db, "events", "recurrence_id", "date")?;
+
+    // The first recurring events only had a periodicity, which is a valid rule
+    if has_column(db, "events", "periodicity")? {
+        db.execute(
+            "update events set rrule = periodicity where rrule is null",
+            params![],
+        )?;
+    }
+    Ok(())
+}
+
+fn add_all_day(db: &Connection) -> rusqlite::Result<()> {
+    add_column(db, "events", "all_day", "boolean not null default 0")
+}
+
+fn add_timezone(db: &Connection) -> rusqlite::Result<()> {
+    add_column(db, "events", "timezone", "text")
+}
+
+fn create_todos(db: &Connection) -> rusqlite::Result<()> {
+    db.execute(
+        r"create table if not exists todos(
+            id integer primary key,
+            description text not null,
+            priority integer not null default 5,
+            due date,
+            completed boolean not null default 0
+        )",
+        params![],
+    )?;
+    Ok(())
+}
+
+fn add_tags(db: &Connection) -> rusqlite::Result<()> {
+    add_column(db, "events", "tags", "text")
+}
+
+fn add_details(db: &Connection) -> rusqlite::Result<()> {
+    add_column(db, "events", "location", "text")?;
+    add_column(db, "events", "notes", "text")?;
+    add_column(db, "events", "url", "text")
+}
+
+fn add_reminders(db: &Connection) -> rusqlite::Result<()> {
+    add_column(db, "events", "reminders", "text")
+}
+
+/// Index the bounds of the events for the range queries
+/// and the overrides by their recurring event
+fn add_indexes(db: &Connection) -> rusqlite::Result<()> {
+    db.execute_batch(
+        "create index if not exists events_start on events(start);
+        create index if not exists events_end on events(end);
+        create index if not exists events_master_id on events(master_id);",
+    )
+}
+
+/// Full-text index of the events kept by `Files`, the rowid is the id of the event
+fn create_search(db: &Connection) -> rusqlite::Result<()> {
+    db.execute_batch(
+        "create virtual table if not exists events_search using fts5(description, notes);
+        delete from events_search;
+        insert into events_search (rowid, description, notes)
+            select id, description, notes from events;",
+    )
+}
+
+/// Journal of the changes to the events to undo and redo them, the events
+/// before and after a change are kept as YAML, `null` if there was none
+fn create_journal(db: &Connection) -> rusqlite::Result<()> {
+    db.execute(
+        r"create table if not exists journal(
+            id integer primary key,
+            change integer not null,
+            event_id integer not null,
+            before text,
+            after text,
+            undone boolean not null default 0
+        )",
+        params![],
+    )?;
+    Ok(())
+}
+
+fn add_trash(db: &Connection) -> rusqlite::Result<()> {
+    add_column(db, "events", "deleted_at", "datetime")
+}
+
+/// Times the events were saved, by which tool, and the versions replaced by updates
+fn add_history(db: &Connection) -> rusqlite::Result<()> {
+    add_column(db, "events", "created_at", "datetime")?;
+    add_column(db, "events", "updated_at", "datetime")?;
+    add_column(db, "events", "updated_by", "text")?;
+    db.execute_batch(
+        "create table if not exists history(
+            id integer primary key,
+            event_id integer not null,
+            event text not null
+        );
+        create index if not exists history_event_id on history(event_id);",
+    )
+}
+
+/// Events saved before there were several calendars go to the default one
+fn add_calendars(db: &Connection) -> rusqlite::Result<()> {
+    add_column(
+        db,
+        "events",
+        "calendar",
+        &format!("text not null default '{}'", DEFAULT_CALENDAR),
+    )
+}
+
+/// The calendars shown or hidden in the app, kept apart from the config edited by hand
+fn create_calendars(db: &Connection) -> rusqlite::Result<()> {
+    db.execute_batch(
+        "create table if not exists calendars (
+            name text primary key,
+            visible integer not null
+        );",
+    )
+}
+
+/// Updates keep the history row they wrote to take it out when undone
+fn add_journal_history(db: &Connection) -> rusqlite::Result<()> {
+    add_column(db, "journal", "history_id", "integer")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{event::Event, files::event_from_row};
+
+    /// Databases written by the releases before the schema was versioned
+    const LEGACY_FIXTURES: &[&str] = &[
+        r"create table events(id integer primary key, description text not null,
+            start datetime not null, end datetime not null);
+        insert into events (description, start, end)
+            values ('Standup', '2023-07-18 10:00:00', '2023-07-18 10:15:00');",
+        r"create table events(id integer primary key, description text not null,
+            start datetime not null, end datetime not null, periodicity text);
+        insert into events (description, start, end, periodicity)
+            values ('Standup', '2023-07-18 10:00:00', '2023-07-18 10:15:00', 'weekly');",
+        r"create table events(id integer primary key, description text not null,
+            start datetime not null, end datetime not null, rrule text, exdate text);
+        insert into events (description, start, end, rrule, exdate)
+            values ('Standup', '2023-07-18 10:00:00', '2023-07-18 10:15:00',
+                'FREQ=WEEKLY;INTERVAL=1', '20230725');",
+        r"create table events(id integer primary key, description text not null,
+            start datetime not null, end datetime not null, rrule text, exdate text,
+            master_id integer references events(id), recurrence_id date,
+            all_day boolean not null default 0, timezone text);
+        insert into events (description, start, end, all_day, timezone)
+            values ('Standup', '2023-07-18 10:00:00', '2023-07-18 10:15:00', 0,
+                'Europe/Berlin');",
+        r"create table events(id integer primary key, description text not null,
+            start datetime not null, end datetime not null, rrule text, exdate text,
+            master_id integer references events(id), recurrence_id date,
+            all_day boolean not null default 0, timezone text, tags text, location text,
+            notes text, url text, reminders text);
+        create table todos(id integer primary key, description text not null,
+            priority integer not null default 5, due date,
+            completed boolean not null default 0);
+        insert into events (description, start, end, tags, reminders)
+            values ('Standup', '2023-07-18 10:00:00', '2023-07-18 10:15:00', 'meeting', '10');",
+    ];
+
+    fn events(db: &Connection) -> Vec<Event> {
+        let mut stmt = db.prepare("select * from events").unwrap();
+        let events = stmt
+            .query_map([], event_from_row)
+            .unwrap()
+            .map(|e| e.unwrap())
+            .collect();
+        events
+    }
+
+    #[test]
+    fn legacy_databases_are_migrated() {
+        for fixture in LEGACY_FIXTURES {
+            let mut db = Connection::open_in_memory().unwrap();
+            db.execute_batch(fixture).unwrap();
+            assert_eq!(user_version(&db).unwrap(), 0);
+
+            migrate(&mut db).unwrap();
+            assert_eq!(user_version(&db).unwrap(), LATEST_VERSION);
+
+            let events = events(&db);
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0].desc(), "Standup");
+            assert_eq!(events[0].calendar(), DEFAULT_CALENDAR);
+        }
+    }
+
+    #[test]
+    fn periodicity_becomes_rrule() {
+        let mut db = Connection::open_in_memory().unwrap();
+        db.execute_batch(LEGACY_FIXTURES[1]).unwrap();
+        migrate(&mut db).unwrap();
+
+        assert!(events(&db)[0].is_recurring());
+    }
+
+    #[test]
+    fn versioned_databases_are_migrated() {
+        for version in 0..=LATEST_VERSION {
+            let mut db = Connection::open_in_memory().unwrap();
+            migrate_to(&mut db, version).unwrap();
+            assert_eq!(user_version(&db).unwrap(), version);
+            if version > 0 {
+                db.execute(
+                    "insert into events (description, start, end)
+                        values ('Standup', '2023-07-18 10:00:00', '2023-07-18 10:15:00')",
+                    params![],
+                )
+                .unwrap();
+            }
+
+            migrate(&mut db).unwrap();
+            assert_eq!(user_version(&db).unwrap(), LATEST_VERSION);
+            assert_eq!(events(&db).len(), if version > 0 { 1 } else { 0 });
+        }
+    }
+
+    #[test]
+    fn newer_database_is_rejected() {
+        let mut db = Connection::open_in_memory().unwrap();
+        db.pragma_update(None, "user_version", LATEST_VERSION + 1)
+            .unwrap();
+
+        assert!(matches!(
+            migrate(&mut db),
+            Err(StorageError::UnsupportedVersion { .. })
+        ));
+    }
+}
@@ -0,0 +1,153 @@
+use std::{env, fs, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+
+const APP_DIR: &str = "rayday";
+const CONFIG_NAME: &str = "config.yml";
+const EVENTS_NAME: &str = "events.db";
+const LOG_NAME: &str = "log.txt";
+const BACKUPS_NAME: &str = "backups";
+
+/// Locations of the files of the app
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paths {
+    pub config: PathBuf,
+    pub db: PathBuf,
+    pub log: PathBuf,
+}
+
+impl Paths {
+    /// Directory of the snapshots of the database, next to it
+    pub fn backups(&self) -> PathBuf {
+        match self.db.parent() {
+            Some(dir) => dir.join(BACKUPS_NAME),
+            None => PathBuf::from(BACKUPS_NAME),
+        }
+    }
+
+    /// Resolve the paths from the flags, then the `RAYDAY_DB` and `RAYDAY_CONFIG`
+    /// variables, then the XDG base directories and create their directories
+    pub fn new(db: Option<PathBuf>, config: Option<PathBuf>) -> Result<Paths> {
+        let home = dirs::home_dir();
+        let mut paths = Paths::resolve(db.clone(), config, |v| env::var(v).ok(), home.clone())?;
+
+        // Databases used to be kept along with the config
+        if let Some(home) = home {
+            let legacy_db = home.join(".config").join(APP_DIR).join(EVENTS_NAME);
+            let overridden = db.is_some() || env::var("RAYDAY_DB").is_ok();
+            if !overridden && !paths.db.exists() && legacy_db.exists() {
+                paths.db = legacy_db;
+            }
+        }
+
+        for path in [&paths.config, &paths.db, &paths.log] {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+        }
+        Ok(paths)
+    }
+
+    fn resolve<F>(
+        db: Option<PathBuf>,
+        config: Option<PathBuf>,
+        var: F,
+        home: Option<PathBuf>,
+    ) -> Result<Paths>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        // Directory from the XDG variable or its default under $HOME
+        let base_dir = |xdg: &str, default: &str| -> Result<PathBuf> {
+            match var(xdg).filter(|d| !d.is_empty()) {
+                Some(dir) => Ok(PathBuf::from(dir).join(APP_DIR)),
+                None => home
+                    .as_ref()
+                    .map(|h| h.join(default).join(APP_DIR))
+                    .ok_or_else(|| anyhow!("No $HOME directory found for {}", xdg)),
+            }
+        };
+
+        let config = match config.or_else(|| var("RAYDAY_CONFIG").map(PathBuf::from)) {
+            Some(config) => config,
+            None => base_dir("XDG_CONFIG_HOME", ".config")?.join(CONFIG_NAME),
+        };
+        let db = match db.or_else(|| var("RAYDAY_DB").map(PathBuf::from)) {
+            Some(db) => db,
+            None => base_dir("XDG_DATA_HOME", ".local/share")?.join(EVENTS_NAME),
+        };
+        let log = base_dir("XDG_STATE_HOME", ".local/state")?.join(LOG_NAME);
+
+        Ok(Paths { config, db, log })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn resolve(db: Option<&str>, config: Option<&str>, vars: &[(&str, &str)]) -> Result<Paths> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Paths::resolve(
+            db.map(PathBuf::from),
+            config.map(PathBuf::from),
+            |v| vars.get(v).cloned(),
+            Some(PathBuf::from("/home/user")),
+        )
+    }
+
+    #[test]
+    fn default_paths() {
+        let paths = resolve(None, None, &[]).unwrap();
+        assert_eq!(
+            paths.config,
+            PathBuf::from("/home/user/.config/rayday/config.yml")
+        );
+        assert_eq!(
+            paths.db,
+            PathBuf::from("/home/user/.local/share/rayday/events.db")
+        );
+        assert_eq!(
+            paths.log,
+            PathBuf::from("/home/user/.local/state/rayday/log.txt")
+        );
+    }
+
+    #[test]
+    fn xdg_paths() {
+        let paths = resolve(
+            None,
+            None,
+            &[
+                ("XDG_CONFIG_HOME", "/xdg/config"),
+                ("XDG_DATA_HOME", "/xdg/data"),
+                ("XDG_STATE_HOME", "/xdg/state"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(paths.config, PathBuf::from("/xdg/config/rayday/config.yml"));
+        assert_eq!(paths.db, PathBuf::from("/xdg/data/rayday/events.db"));
+        assert_eq!(paths.log, PathBuf::from("/xdg/state/rayday/log.txt"));
+    }
+
+    #[test]
+    fn flags_override_variables() {
+        let vars = [
+            ("XDG_DATA_HOME", "/xdg/data"),
+            ("RAYDAY_DB", "/tmp/env.db"),
+            ("RAYDAY_CONFIG", "/tmp/env.yml"),
+        ];
+
+        let paths = resolve(None, None, &vars).unwrap();
+        assert_eq!(paths.db, PathBuf::from("/tmp/env.db"));
+        assert_eq!(paths.config, PathBuf::from("/tmp/env.yml"));
+
+        let paths = resolve(Some("work.db"), Some("work.yml"), &vars).unwrap();
+        assert_eq!(paths.db, PathBuf::from("work.db"));
+        assert_eq!(paths.config, PathBuf::from("work.yml"));
+    }
+}
@@ -7,22 +7,37 @@ use crate::{
     widgets::{calendar::CalendarState, event_view::EventViewState},
 };
 
+mod goto;
 mod input;
 mod normal;
+mod search;
 mod select;
+mod todo;
+mod trash;
 
 pub fn handle<'a>(key: KeyEvent, mut app: App<'a>) -> Result<App<'a>> {
+    // Any key dismisses an alert
+    if app.alert.take().is_some() {
+        return Ok(app);
+    }
+
     // TODO: implement handlers to return Result<App<'a>> insted of App<'a>
     // to get rid of `let app ...`
-    let app = match app.input_mode.current() {
+    let mut app = match app.input_mode.current() {
         Some(m) => match m {
             InputMode::Normal => normal::handle(key, app),
             InputMode::Select => select::handle(key, app),
             InputMode::Input => input::handle(key, app),
+            InputMode::TodoInput => todo::handle_input(key, app),
+            InputMode::Goto => goto::handle(key, app),
+            InputMode::Search => search::handle(key, app),
             _ => app,
         },
         None => app,
     };
 
+    // Whatever a key press changed is undone at once
+    app.store.end_change();
+    app.refresh();
     Ok(app)
 }
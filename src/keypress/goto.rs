@@ -0,0 +1,75 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::{
+    app::{App, InputMode},
+    event::Event,
+    store::StorageError,
+    widgets::calendar::CalendarState,
+};
+
+/// Handler for the prompt of the event id to jump to
+pub fn handle<'a>(key: KeyEvent, mut app: App<'a>) -> App<'a> {
+    match key.code {
+        KeyCode::Char(c) if c.is_ascii_digit() => {
+            app.state_popup.goto.push(c);
+            app
+        }
+        KeyCode::Backspace => {
+            app.state_popup.goto.pop();
+            app
+        }
+        KeyCode::Enter => on_finish(app),
+        KeyCode::Esc => on_exit(app),
+        _ => app,
+    }
+}
+
+pub fn on_finish<'a>(app: App<'a>) -> App<'a> {
+    let id = app.state_popup.goto.parse::<usize>().ok();
+    let app = on_exit(app);
+    match id {
+        Some(id) => jump_to_event(app, id),
+        None => app,
+    }
+}
+
+pub fn on_exit<'a>(mut app: App<'a>) -> App<'a> {
+    app.input_mode.restore();
+    app.state_popup.clear();
+    app.state_popup.visible = false;
+    app
+}
+
+/// Select the date of the event with `id` and the event itself
+pub fn jump_to_event<'a>(mut app: App<'a>, id: usize) -> App<'a> {
+    let event = match app.store.get_event(id) {
+        Ok(Some(event)) => event,
+        Ok(None) => {
+            app.report("Could not find the event", StorageError::NotFound(id));
+            return app;
+        }
+        Err(e) => {
+            app.report("Could not find the event", e);
+            return app;
+        }
+    };
+
+    select_event(app, &event)
+}
+
+/// Select the date of the event and the event itself
+pub fn select_event<'a>(mut app: App<'a>, event: &Event) -> App<'a> {
+    app.state_calendar = CalendarState::new(event.local_start().date());
+    app.state_events.select(None);
+    app.load_events();
+    let selected = app
+        .state_events
+        .events
+        .iter()
+        .position(|e| e.id() == event.id());
+    if selected.is_some() {
+        app.input_mode.store(InputMode::Select);
+        app.state_events.select(selected);
+    }
+    app
+}
@@ -1,11 +1,12 @@
 use chrono::Duration;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use log2::{error, info};
+use log2::info;
 
 use crate::{
     app::{App, InputMode},
-    popup::input::PopupInputState,
-    widgets::{calendar::CalendarState, event_view::EventViewState},
+    event::EditScope,
+    popup::{input::PopupInputState, state::PopupAction},
+    widgets::calendar::CalendarState,
 };
 
 pub fn handle<'a>(key: KeyEvent, mut app: App<'a>) -> App<'a> {
@@ -13,9 +14,9 @@ pub fn handle<'a>(key: KeyEvent, mut app: App<'a>) -> App<'a> {
         KeyCode::Char(c) => on_key(c, app),
         KeyCode::Left => on_left(app),
         KeyCode::Right => on_right(app),
-        KeyCode::Enter | KeyCode::Tab => on_next(app),
+        KeyCode::Enter => on_enter(app),
+        KeyCode::Tab => on_next(app),
         KeyCode::BackTab => on_previous(app),
-        KeyCode::Tab | KeyCode::Enter => on_next(app),
         KeyCode::Backspace => on_erase(app),
         KeyCode::Esc => on_exit(app),
         _ => app,
@@ -23,44 +24,88 @@ pub fn handle<'a>(key: KeyEvent, mut app: App<'a>) -> App<'a> {
 }
 
 pub fn on_left<'a>(mut app: App<'a>) -> App<'a> {
-    info!("Left in Input InputMode is not implemented");
+    match (app.state_popup.input.state, app.state_popup.input.scope) {
+        (PopupInputState::Scope, Some(scope)) => {
+            app.state_popup.input.scope = Some(scope.previous())
+        }
+        _ => info!("Left in Input InputMode is not implemented"),
+    }
     app
 }
 
 pub fn on_right<'a>(mut app: App<'a>) -> App<'a> {
-    info!("Right in Input InputMode is not implemented");
+    match (app.state_popup.input.state, app.state_popup.input.scope) {
+        (PopupInputState::Scope, Some(scope)) => app.state_popup.input.scope = Some(scope.next()),
+        _ => info!("Right in Input InputMode is not implemented"),
+    }
     app
 }
 
 pub fn on_key<'a>(c: char, mut app: App<'a>) -> App<'a> {
+    if app.state_popup.action == PopupAction::Delete {
+        return app;
+    }
     match app.state_popup.input.state {
+        PopupInputState::Scope => {}
         PopupInputState::StartDate => app.state_popup.input.start_date.push(c),
         PopupInputState::StartTime => app.state_popup.input.start_time.push(c),
         PopupInputState::EndDate => app.state_popup.input.end_date.push(c),
         PopupInputState::EndTime => app.state_popup.input.end_time.push(c),
+        PopupInputState::Recurrence => app.state_popup.input.recurrence.push(c),
+        PopupInputState::TimeZone => app.state_popup.input.timezone.push(c),
+        PopupInputState::Reminders => app.state_popup.input.reminders.push(c),
         PopupInputState::Description => app.state_popup.input.description.push(c),
+        PopupInputState::Calendar => app.state_popup.input.calendar.push(c),
+        PopupInputState::Tags => app.state_popup.input.tags.push(c),
+        PopupInputState::Location => app.state_popup.input.location.push(c),
+        PopupInputState::Url => app.state_popup.input.url.push(c),
+        PopupInputState::Notes => app.state_popup.input.notes.push(c),
     }
     app
 }
 
 pub fn on_erase<'a>(mut app: App<'a>) -> App<'a> {
+    if app.state_popup.action == PopupAction::Delete {
+        return app;
+    }
     match app.state_popup.input.state {
+        PopupInputState::Scope => None,
         PopupInputState::StartDate => app.state_popup.input.start_date.pop(),
         PopupInputState::StartTime => app.state_popup.input.start_time.pop(),
         PopupInputState::EndDate => app.state_popup.input.end_date.pop(),
         PopupInputState::EndTime => app.state_popup.input.end_time.pop(),
+        PopupInputState::Recurrence => app.state_popup.input.recurrence.pop(),
+        PopupInputState::TimeZone => app.state_popup.input.timezone.pop(),
+        PopupInputState::Reminders => app.state_popup.input.reminders.pop(),
         PopupInputState::Description => app.state_popup.input.description.pop(),
+        PopupInputState::Calendar => app.state_popup.input.calendar.pop(),
+        PopupInputState::Tags => app.state_popup.input.tags.pop(),
+        PopupInputState::Location => app.state_popup.input.location.pop(),
+        PopupInputState::Url => app.state_popup.input.url.pop(),
+        PopupInputState::Notes => app.state_popup.input.notes.pop(),
     };
     app
 }
 
 pub fn on_next<'a>(mut app: App<'a>) -> App<'a> {
+    if app.state_popup.action == PopupAction::Delete {
+        return on_finish(app);
+    }
     match app.state_popup.input.state {
+        PopupInputState::Scope => app.state_popup.input.state = PopupInputState::StartDate,
         PopupInputState::StartDate => app.state_popup.input.state = PopupInputState::StartTime,
         PopupInputState::StartTime => app.state_popup.input.state = PopupInputState::EndDate,
         PopupInputState::EndDate => app.state_popup.input.state = PopupInputState::EndTime,
-        PopupInputState::EndTime => app.state_popup.input.state = PopupInputState::Description,
-        PopupInputState::Description => {
+        PopupInputState::EndTime => app.state_popup.input.state = PopupInputState::Recurrence,
+        PopupInputState::Recurrence => app.state_popup.input.state = PopupInputState::TimeZone,
+        PopupInputState::TimeZone => app.state_popup.input.state = PopupInputState::Reminders,
+        PopupInputState::Reminders => app.state_popup.input.state = PopupInputState::Description,
+        PopupInputState::Description => app.state_popup.input.state = PopupInputState::Calendar,
+        PopupInputState::Calendar => app.state_popup.input.state = PopupInputState::Tags,
+        PopupInputState::Tags => app.state_popup.input.state = PopupInputState::Location,
+        PopupInputState::Location => app.state_popup.input.state = PopupInputState::Url,
+        PopupInputState::Url => app.state_popup.input.state = PopupInputState::Notes,
+        PopupInputState::Notes => {
             app.state_popup.input.state = PopupInputState::StartDate;
             app = on_finish(app);
         }
@@ -68,15 +113,40 @@ pub fn on_next<'a>(mut app: App<'a>) -> App<'a> {
     app
 }
 
+/// Start a new line in the notes, otherwise go to the next field
+pub fn on_enter<'a>(mut app: App<'a>) -> App<'a> {
+    match app.state_popup.input.state {
+        PopupInputState::Notes if app.state_popup.action != PopupAction::Delete => {
+            app.state_popup.input.notes.push('\n');
+            app
+        }
+        _ => on_next(app),
+    }
+}
+
 pub fn on_previous<'a>(mut app: App<'a>) -> App<'a> {
     match app.state_popup.input.state {
+        PopupInputState::Scope => {
+            app = on_exit(app);
+        }
+        PopupInputState::StartDate if app.state_popup.input.scope.is_some() => {
+            app.state_popup.input.state = PopupInputState::Scope
+        }
         PopupInputState::StartDate => {
             app = on_exit(app);
         }
         PopupInputState::StartTime => app.state_popup.input.state = PopupInputState::StartDate,
         PopupInputState::EndDate => app.state_popup.input.state = PopupInputState::StartTime,
         PopupInputState::EndTime => app.state_popup.input.state = PopupInputState::EndDate,
-        PopupInputState::Description => app.state_popup.input.state = PopupInputState::EndTime,
+        PopupInputState::Recurrence => app.state_popup.input.state = PopupInputState::EndTime,
+        PopupInputState::TimeZone => app.state_popup.input.state = PopupInputState::Recurrence,
+        PopupInputState::Reminders => app.state_popup.input.state = PopupInputState::TimeZone,
+        PopupInputState::Description => app.state_popup.input.state = PopupInputState::Reminders,
+        PopupInputState::Calendar => app.state_popup.input.state = PopupInputState::Description,
+        PopupInputState::Tags => app.state_popup.input.state = PopupInputState::Calendar,
+        PopupInputState::Location => app.state_popup.input.state = PopupInputState::Tags,
+        PopupInputState::Url => app.state_popup.input.state = PopupInputState::Location,
+        PopupInputState::Notes => app.state_popup.input.state = PopupInputState::Url,
     }
     app
 }
@@ -85,39 +155,69 @@ pub fn on_previous<'a>(mut app: App<'a>) -> App<'a> {
 /// it in the db. Then clears popup input and loads
 /// events for selected date.
 pub fn on_finish<'a>(mut app: App<'a>) -> App<'a> {
-    app.input_mode.restore();
-    match app.input_mode.current() {
-        Some(InputMode::Normal) => {
-            let event = app.state_popup.input.parse(None).unwrap();
-            app.files.add_event(event).unwrap();
-        }
-        Some(InputMode::Select) => {
-            if let Some(selected_idx) = app.state_events.selected {
-                let selected_event_id = app
-                    .state_events
-                    .events
-                    .get(selected_idx)
-                    .expect("No event selected")
-                    .id();
-                let selected_event = app
-                    .state_popup
-                    .input
-                    .parse(selected_event_id)
-                    .expect("Could not parse popup input");
-                app.files.update_event(selected_event).unwrap();
-                app.state_events = EventViewState::new(
-                    Some(selected_idx),
-                    app.files
-                        .get_events_on_date(app.state_calendar.get_selected_date()),
-                );
-            } else {
+    let adding = matches!(
+        app.input_mode.seq.iter().rev().nth(1),
+        Some(InputMode::Normal)
+    );
+    let occurrence = match adding {
+        true => None,
+        false => app.state_events.selected_event().cloned(),
+    };
+
+    let event = match app.state_popup.action {
+        PopupAction::Delete => None,
+        _ => match app
+            .state_popup
+            .input
+            .parse(occurrence.as_ref().and_then(|o| o.id()))
+        {
+            Ok(event) => Some(event),
+            // Keep the popup open to correct the input
+            Err(e) => {
+                app.report("Could not parse the event", e);
+                return app;
             }
+        },
+    };
+
+    // Events go to the calendars of the config unless they stay where they are
+    if let Some(event) = &event {
+        let moved = occurrence
+            .as_ref()
+            .is_none_or(|o| o.calendar() != event.calendar());
+        if let (true, Err(e)) = (moved, app.config.check_calendar(event.calendar())) {
+            app.report("Could not save the event", e);
+            return app;
         }
-        Some(InputMode::Input) => {
-            error!("App state must not return to Input mode after finishing popup input")
-        }
-        None => {
-            error!("After finishing popup input app state must return to some InputMode, got None")
+    }
+
+    let scope = app.state_popup.input.scope;
+    let result = match (occurrence, event) {
+        (None, Some(event)) => app.store.add_event(event).map(|_| ()),
+        (Some(occurrence), None) => app
+            .store
+            .remove_event_in_scope(&occurrence, scope.unwrap_or(EditScope::This)),
+        (Some(occurrence), Some(event)) => match scope {
+            Some(scope) => app.store.update_event_in_scope(&occurrence, event, scope),
+            None => app.store.update_event(event),
+        },
+        (None, None) => Ok(()),
+    };
+    if let Err(e) = result {
+        app.report("Could not save the event", e);
+    }
+
+    app.input_mode.restore();
+    let selected = app.state_events.selected;
+    app.load_events();
+    if !adding {
+        // Keep the selection in bounds or leave the select mode without events
+        let len = app.state_events.events.len();
+        if len == 0 {
+            app.input_mode.restore();
+            app.state_events.select(None);
+        } else {
+            app.state_events.select(selected.map(|i| i.min(len - 1)));
         }
     }
 
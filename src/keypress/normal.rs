@@ -1,14 +1,35 @@
 use chrono::Duration;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use log2::info;
 
+use super::{todo, trash};
 use crate::{
-    app::{App, InputMode},
+    app::{App, InputMode, TODO_TAB, TRASH_TAB},
+    popup::state::PopupAction,
     widgets::{calendar::CalendarState, event_view::EventViewState},
 };
 
 /// Main handler for the normal input mode
 pub fn handle<'a>(key: KeyEvent, mut app: App<'a>) -> App<'a> {
     match key.code {
+        KeyCode::Tab => {
+            app.state_tabs.next();
+            return on_tab(app);
+        }
+        KeyCode::BackTab => {
+            app.state_tabs.previous();
+            return on_tab(app);
+        }
+        _ => {}
+    }
+    match app.state_tabs.index {
+        TODO_TAB => return todo::handle(key, app),
+        TRASH_TAB => return trash::handle(key, app),
+        _ => {}
+    }
+
+    match key.code {
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => on_redo(app),
         KeyCode::Char(c) => on_key(c, app),
         KeyCode::Up => on_up(app),
         KeyCode::Down => on_down(app),
@@ -19,6 +40,14 @@ pub fn handle<'a>(key: KeyEvent, mut app: App<'a>) -> App<'a> {
     }
 }
 
+/// Show what was deleted since the trash was shown last
+fn on_tab<'a>(mut app: App<'a>) -> App<'a> {
+    if app.state_tabs.index == TRASH_TAB {
+        app.load_trash();
+    }
+    app
+}
+
 /// Update calendar state by one week earlier
 pub fn on_up<'a>(mut app: App<'a>) -> App<'a> {
     app.state_calendar = CalendarState::new(
@@ -27,11 +56,8 @@ pub fn on_up<'a>(mut app: App<'a>) -> App<'a> {
             .checked_sub_signed(Duration::weeks(1))
             .unwrap(),
     );
-    app.state_events = EventViewState::new(
-        None,
-        app.files
-            .get_events_on_date(app.state_calendar.get_selected_date()),
-    );
+    app.state_events = EventViewState::new(None, Vec::new());
+    app.load_events();
     app
 }
 
@@ -43,11 +69,8 @@ pub fn on_down<'a>(mut app: App<'a>) -> App<'a> {
             .checked_add_signed(Duration::weeks(1))
             .unwrap(),
     );
-
-    let events = app
-        .files
-        .get_events_on_date(app.state_calendar.get_selected_date());
-    app.state_events = EventViewState::new(None, events);
+    app.state_events = EventViewState::new(None, Vec::new());
+    app.load_events();
     app
 }
 
@@ -59,11 +82,8 @@ pub fn on_left<'a>(mut app: App<'a>) -> App<'a> {
             .checked_sub_signed(Duration::days(1))
             .unwrap(),
     );
-    app.state_events = EventViewState::new(
-        None,
-        app.files
-            .get_events_on_date(app.state_calendar.get_selected_date()),
-    );
+    app.state_events = EventViewState::new(None, Vec::new());
+    app.load_events();
     app
 }
 
@@ -75,11 +95,44 @@ pub fn on_right<'a>(mut app: App<'a>) -> App<'a> {
             .checked_add_signed(Duration::days(1))
             .unwrap(),
     );
-    app.state_events = EventViewState::new(
-        None,
-        app.files
-            .get_events_on_date(app.state_calendar.get_selected_date()),
-    );
+    app.state_events = EventViewState::new(None, Vec::new());
+    app.load_events();
+    app
+}
+
+/// Undo the last change to the events
+pub fn on_undo<'a>(mut app: App<'a>) -> App<'a> {
+    match app.store.undo() {
+        Ok(true) => app.load_events(),
+        Ok(false) => info!("Nothing to undo"),
+        Err(e) => app.report("Could not undo", e),
+    }
+    app
+}
+
+/// Make the last undone change to the events again
+pub fn on_redo<'a>(mut app: App<'a>) -> App<'a> {
+    match app.store.redo() {
+        Ok(true) => app.load_events(),
+        Ok(false) => info!("Nothing to redo"),
+        Err(e) => app.report("Could not redo", e),
+    }
+    app
+}
+
+/// Show or hide the calendar at `index` in the config,
+/// it stays so the next time the app is opened
+pub fn on_toggle_calendar<'a>(index: usize, mut app: App<'a>) -> App<'a> {
+    let toggled = app
+        .config
+        .toggle_calendar(index)
+        .map(|c| (c.name.clone(), c.visible));
+    if let Some((name, visible)) = toggled {
+        if let Err(e) = app.store.set_calendar_visible(&name, visible) {
+            app.report("Could not save the calendar", e);
+        }
+        app.load_events();
+    }
     app
 }
 
@@ -94,6 +147,9 @@ pub fn on_key<'a>(c: char, mut app: App<'a>) -> App<'a> {
         'k' => on_up(app),
         'h' => on_left(app),
         'l' => on_right(app),
+        'u' => on_undo(app),
+        /// Toggle the calendars by their numbers in the list
+        c @ '1'..='9' => on_toggle_calendar(c as usize - '1' as usize, app),
         /// Go into select mode
         's' => {
             if app.state_events.events.len() > 0 {
@@ -108,6 +164,19 @@ pub fn on_key<'a>(c: char, mut app: App<'a>) -> App<'a> {
             app.state_popup
                 .input
                 .set_date(app.state_calendar.get_selected_date());
+            app.state_popup.input.calendar = app.config.default_calendar().to_string();
+            app.state_popup.visible = true;
+            app
+        }
+        /// Search events as the query is typed
+        '/' => {
+            app.input_mode.store(InputMode::Search);
+            app
+        }
+        /// Ask for the id of an event to jump to
+        'g' => {
+            app.input_mode.store(InputMode::Goto);
+            app.state_popup.action = PopupAction::Goto;
             app.state_popup.visible = true;
             app
         }
@@ -0,0 +1,121 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::goto::select_event;
+use crate::{
+    app::{App, InputMode},
+    widgets::search_view::SearchViewState,
+};
+
+/// Handler for the incremental search of events
+pub fn handle<'a>(key: KeyEvent, mut app: App<'a>) -> App<'a> {
+    match key.code {
+        KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => on_down(app),
+        KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => on_up(app),
+        KeyCode::Char(c) => {
+            app.state_search.query.push(c);
+            update_results(app)
+        }
+        KeyCode::Backspace => {
+            app.state_search.query.pop();
+            update_results(app)
+        }
+        KeyCode::Down | KeyCode::Tab => on_down(app),
+        KeyCode::Up | KeyCode::BackTab => on_up(app),
+        KeyCode::Enter => on_finish(app),
+        KeyCode::Esc => on_exit(app),
+        _ => app,
+    }
+}
+
+/// Search the events matching the query typed so far
+fn update_results<'a>(mut app: App<'a>) -> App<'a> {
+    match app.store.search_events(&app.state_search.query) {
+        Ok(results) => {
+            let selected = if results.is_empty() { None } else { Some(0) };
+            app.state_search.results = results;
+            app.state_search.select(selected);
+        }
+        Err(e) => app.report("Could not search events", e),
+    }
+    app
+}
+
+pub fn on_up<'a>(mut app: App<'a>) -> App<'a> {
+    let selected = app.state_search.selected.map(|i| i.saturating_sub(1));
+    app.state_search.select(selected);
+    app
+}
+
+pub fn on_down<'a>(mut app: App<'a>) -> App<'a> {
+    let len = app.state_search.results.len();
+    let selected = app.state_search.selected.map(|i| (i + 1).min(len - 1));
+    app.state_search.select(selected);
+    app
+}
+
+/// Jump to the date of the chosen event and select it
+pub fn on_finish<'a>(mut app: App<'a>) -> App<'a> {
+    let event = app.state_search.selected_event().cloned();
+    app = on_exit(app);
+    match event {
+        Some(event) => select_event(app, &event),
+        None => app,
+    }
+}
+
+pub fn on_exit<'a>(mut app: App<'a>) -> App<'a> {
+    app.input_mode.restore();
+    app.state_search = SearchViewState::default();
+    app
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event::Event,
+        files::Config,
+        store::{memory::MemoryStore, EventStore},
+    };
+    use chrono::NaiveDate;
+
+    fn type_keys<'a>(keys: &[KeyCode], mut app: App<'a>) -> App<'a> {
+        for key in keys {
+            app = handle(KeyEvent::new(*key, KeyModifiers::NONE), app);
+        }
+        app
+    }
+
+    #[test]
+    fn search_jumps_to_the_chosen_event() {
+        let mut store = MemoryStore::new();
+        let date = NaiveDate::from_ymd_opt(2021, 3, 2).unwrap();
+        for (desc, hour) in [("Lunch", 12), ("Dentist", 15)] {
+            let start = date.and_hms_opt(hour, 0, 0).unwrap();
+            store
+                .add_event(Event::new(None, String::from(desc), start, start))
+                .unwrap();
+        }
+
+        let mut app = App::new("RayDay", false, store, Config::default());
+        app.input_mode.store(InputMode::Search);
+        let app = type_keys(
+            &[
+                KeyCode::Char('d'),
+                KeyCode::Char('e'),
+                KeyCode::Char('x'),
+                KeyCode::Backspace,
+            ],
+            app,
+        );
+        assert_eq!(app.state_search.results.len(), 1);
+
+        let app = type_keys(&[KeyCode::Enter], app);
+        assert_eq!(app.state_calendar.get_selected_date(), date);
+        assert!(matches!(app.input_mode.current(), Some(InputMode::Select)));
+        assert_eq!(
+            app.state_events.selected_event().map(|e| e.desc()),
+            Some(String::from("Dentist"))
+        );
+    }
+}
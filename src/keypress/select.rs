@@ -1,18 +1,49 @@
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
-use crate::app::{App, InputMode};
+use super::normal;
+use crate::{
+    app::{App, InputMode},
+    popup::state::PopupAction,
+};
 
 pub fn handle<'a>(key: KeyEvent, mut app: App<'a>) -> App<'a> {
     match key.code {
         KeyCode::Up => on_up(app),
         KeyCode::Down => on_down(app),
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            normal::on_redo(on_leave(app))
+        }
         KeyCode::Char(c) => on_key(c, app),
+        KeyCode::Enter => on_key('i', app),
         KeyCode::Esc => on_key('q', app),
         _ => app,
     }
 }
 
+/// Go back to the normal mode
+pub fn on_leave<'a>(mut app: App<'a>) -> App<'a> {
+    app.state_events.show_details = false;
+    app.state_events.history = None;
+    app.state_events.selected = None;
+    app.input_mode.restore();
+    app
+}
+
+pub fn on_history<'a>(mut app: App<'a>) -> App<'a> {
+    if app.state_events.history.take().is_some() {
+        return app;
+    }
+    if let Some(id) = app.state_events.selected_event().and_then(|e| e.id()) {
+        match app.store.get_history(id) {
+            Ok(versions) => app.state_events.history = Some(versions),
+            Err(e) => app.report("Could not load the history", e),
+        }
+    }
+    app
+}
+
 pub fn on_up<'a>(mut app: App<'a>) -> App<'a> {
+    app.state_events.history = None;
     app.state_events.selected = if let Some(sel) = app.state_events.selected {
         Some(sel.saturating_sub(1))
     } else {
@@ -23,21 +54,34 @@ pub fn on_up<'a>(mut app: App<'a>) -> App<'a> {
 }
 
 pub fn on_down<'a>(mut app: App<'a>) -> App<'a> {
-    let events = &app.state_events.events;
-    app.state_events.selected = if let Some(sel) = app.state_events.selected {
-        if sel == events.len() - 1 {
-            Some(sel)
-        } else {
-            Some(sel.saturating_add(1))
-        }
-    } else {
-        Some(0)
+    app.state_events.history = None;
+    let last = match app.state_events.events.len().checked_sub(1) {
+        Some(last) => last,
+        None => return app,
+    };
+    app.state_events.selected = match app.state_events.selected {
+        Some(sel) => Some(sel.saturating_add(1).min(last)),
+        None => Some(0),
     };
     app
 }
 
 pub fn on_delete<'a>(mut app: App<'a>) -> App<'a> {
     if let Some(selected_idx) = app.state_events.selected {
+        // Ask which occurrences of a recurring event to delete
+        let event = match app.state_events.events.get(selected_idx) {
+            Some(event) => event,
+            None => return app,
+        };
+        if event.is_recurring() {
+            app.state_popup.input.load(event);
+            app.state_popup.input.ask_scope();
+            app.state_popup.action = PopupAction::Delete;
+            app.input_mode.store(InputMode::Input);
+            app.state_popup.visible = true;
+            return app;
+        }
+
         let date = app.state_calendar.get_selected_date();
 
         let selected_event = app.state_events.events.remove(selected_idx);
@@ -51,19 +95,21 @@ pub fn on_delete<'a>(mut app: App<'a>) -> App<'a> {
             app.state_events.selected = None;
         }
 
-        app.files.remove_event(selected_event.id().unwrap());
+        if let Some(Err(e)) = selected_event.id().map(|id| app.store.remove_event(id)) {
+            app.report("Could not remove the event", e);
+            app.load_events();
+        }
     }
     app
 }
 
 pub fn on_edit<'a>(mut app: App<'a>) -> App<'a> {
-    if let Some(selected) = app.state_events.selected {
-        let event = app
-            .state_events
-            .events
-            .get(selected)
-            .expect("No event was selected");
+    if let Some(event) = app.state_events.selected_event() {
         app.state_popup.input.load(event);
+        if event.is_recurring() {
+            app.state_popup.input.ask_scope();
+        }
+        app.state_popup.action = PopupAction::Edit;
         app.input_mode.store(InputMode::Input);
         app.state_popup.visible = true
     }
@@ -72,15 +118,28 @@ pub fn on_edit<'a>(mut app: App<'a>) -> App<'a> {
 
 pub fn on_key<'a>(c: char, mut app: App<'a>) -> App<'a> {
     match c {
-        'q' => {
-            app.state_events.selected = None;
-            app.input_mode.restore();
+        'q' if app.state_events.history.is_some() => {
+            app.state_events.history = None;
+            app
+        }
+        'q' if app.state_events.show_details => {
+            app.state_events.show_details = false;
             app
         }
+        'q' => on_leave(app),
+        // The selected event may be gone after undoing
+        'u' => normal::on_undo(on_leave(app)),
         'j' => on_down(app),
         'k' => on_up(app),
         'd' => on_delete(app),
         'e' => on_edit(app),
+        /// Show or hide the details of the selected event
+        'i' => {
+            app.state_events.show_details = !app.state_events.show_details;
+            app
+        }
+        /// Show or hide the history of the selected event
+        'h' => on_history(app),
         _ => app,
     }
 }
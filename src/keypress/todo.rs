@@ -0,0 +1,191 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::{
+    app::{App, InputMode},
+    popup::{state::PopupAction, todo::TodoInputState},
+    todo::Todo,
+};
+
+/// Handler for the normal input mode on the todo tab
+pub fn handle<'a>(key: KeyEvent, mut app: App<'a>) -> App<'a> {
+    match key.code {
+        KeyCode::Char(c) => on_key(c, app),
+        KeyCode::Up => on_up(app),
+        KeyCode::Down => on_down(app),
+        KeyCode::Enter => on_key('e', app),
+        _ => app,
+    }
+}
+
+/// Select the previous todo
+pub fn on_up<'a>(mut app: App<'a>) -> App<'a> {
+    let selected = match app.state_todos.selected {
+        Some(i) if i > 0 => Some(i - 1),
+        Some(i) => Some(i),
+        None if !app.state_todos.todos.is_empty() => Some(0),
+        None => None,
+    };
+    app.state_todos.select(selected);
+    app
+}
+
+/// Select the next todo
+pub fn on_down<'a>(mut app: App<'a>) -> App<'a> {
+    let len = app.state_todos.todos.len();
+    let selected = match app.state_todos.selected {
+        Some(i) if i + 1 < len => Some(i + 1),
+        Some(i) => Some(i),
+        None if len > 0 => Some(0),
+        None => None,
+    };
+    app.state_todos.select(selected);
+    app
+}
+
+pub fn on_key<'a>(c: char, mut app: App<'a>) -> App<'a> {
+    match c {
+        'q' => {
+            app.should_quit = true;
+            app
+        }
+        'j' => on_down(app),
+        'k' => on_up(app),
+        /// Add a new todo
+        'a' => {
+            app.input_mode.store(InputMode::TodoInput);
+            app.state_popup.action = PopupAction::AddTodo;
+            app.state_popup.visible = true;
+            app
+        }
+        /// Edit the selected todo
+        'e' => {
+            if let Some(todo) = app.state_todos.selected_todo().cloned() {
+                app.input_mode.store(InputMode::TodoInput);
+                app.state_popup.todo.load(&todo);
+                app.state_popup.action = PopupAction::EditTodo;
+                app.state_popup.visible = true;
+            }
+            app
+        }
+        /// Mark the selected todo as completed or not completed
+        'c' | ' ' => update_selected(app, |todo| todo.toggle_completed()),
+        '+' => update_selected(app, |todo| todo.raise_priority()),
+        '-' => update_selected(app, |todo| todo.lower_priority()),
+        'd' => {
+            if let Some(id) = app.state_todos.selected_todo().and_then(|t| t.id()) {
+                if let Err(e) = app.store.remove_todo(id) {
+                    app.report("Could not remove the todo", e);
+                }
+                reload(&mut app);
+            }
+            app
+        }
+        _ => app,
+    }
+}
+
+/// Apply a change to the selected todo and save it
+fn update_selected<'a, F>(mut app: App<'a>, f: F) -> App<'a>
+where
+    F: FnOnce(&mut Todo),
+{
+    if let Some(mut todo) = app.state_todos.selected_todo().cloned() {
+        f(&mut todo);
+        let id = todo.id();
+        if let Err(e) = app.store.update_todo(todo) {
+            app.report("Could not update the todo", e);
+        }
+        reload(&mut app);
+        // Follow the todo after resorting the list
+        let selected = app.state_todos.todos.iter().position(|t| t.id() == id);
+        app.state_todos.select(selected);
+    }
+    app
+}
+
+/// Load todos from the db keeping the selection in bounds
+fn reload(app: &mut App) {
+    app.load_todos();
+    let len = app.state_todos.todos.len();
+    let selected = match app.state_todos.selected {
+        _ if len == 0 => None,
+        Some(i) => Some(i.min(len - 1)),
+        None => None,
+    };
+    app.state_todos.select(selected);
+}
+
+/// Handler for the todo popup
+pub fn handle_input<'a>(key: KeyEvent, mut app: App<'a>) -> App<'a> {
+    match key.code {
+        KeyCode::Char(c) => {
+            app.state_popup.todo.current_mut().push(c);
+            app
+        }
+        KeyCode::Backspace => {
+            app.state_popup.todo.current_mut().pop();
+            app
+        }
+        KeyCode::Enter | KeyCode::Tab => on_next(app),
+        KeyCode::BackTab => on_previous(app),
+        KeyCode::Esc => on_exit(app),
+        _ => app,
+    }
+}
+
+pub fn on_next<'a>(mut app: App<'a>) -> App<'a> {
+    match app.state_popup.todo.state {
+        TodoInputState::Description => app.state_popup.todo.state = TodoInputState::Priority,
+        TodoInputState::Priority => app.state_popup.todo.state = TodoInputState::Due,
+        TodoInputState::Due => app = on_finish(app),
+    }
+    app
+}
+
+pub fn on_previous<'a>(mut app: App<'a>) -> App<'a> {
+    match app.state_popup.todo.state {
+        TodoInputState::Description => app = on_exit(app),
+        TodoInputState::Priority => app.state_popup.todo.state = TodoInputState::Description,
+        TodoInputState::Due => app.state_popup.todo.state = TodoInputState::Priority,
+    }
+    app
+}
+
+/// Parse popup input into a todo and save it in the db
+pub fn on_finish<'a>(mut app: App<'a>) -> App<'a> {
+    match app.state_popup.action {
+        PopupAction::EditTodo => {
+            if let Some(selected) = app.state_todos.selected_todo().cloned() {
+                match app
+                    .state_popup
+                    .todo
+                    .parse(selected.id(), selected.is_completed())
+                {
+                    Ok(todo) => {
+                        if let Err(e) = app.store.update_todo(todo) {
+                            app.report("Could not update the todo", e);
+                        }
+                    }
+                    Err(e) => app.report("Could not parse the todo", e),
+                }
+            }
+        }
+        _ => match app.state_popup.todo.parse(None, false) {
+            Ok(todo) => {
+                if let Err(e) = app.store.add_todo(todo) {
+                    app.report("Could not add the todo", e);
+                }
+            }
+            Err(e) => app.report("Could not parse the todo", e),
+        },
+    }
+    reload(&mut app);
+    on_exit(app)
+}
+
+pub fn on_exit<'a>(mut app: App<'a>) -> App<'a> {
+    app.input_mode.restore();
+    app.state_popup.clear();
+    app.state_popup.visible = false;
+    app
+}
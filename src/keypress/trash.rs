@@ -0,0 +1,70 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::app::App;
+
+/// Handler for the normal input mode on the trash tab
+pub fn handle<'a>(key: KeyEvent, app: App<'a>) -> App<'a> {
+    match key.code {
+        KeyCode::Char(c) => on_key(c, app),
+        KeyCode::Up => on_up(app),
+        KeyCode::Down => on_down(app),
+        _ => app,
+    }
+}
+
+/// Select the previous deleted event
+pub fn on_up<'a>(mut app: App<'a>) -> App<'a> {
+    let selected = match app.state_trash.selected {
+        Some(i) => Some(i.saturating_sub(1)),
+        None if !app.state_trash.events.is_empty() => Some(0),
+        None => None,
+    };
+    app.state_trash.select(selected);
+    app
+}
+
+/// Select the next deleted event
+pub fn on_down<'a>(mut app: App<'a>) -> App<'a> {
+    let len = app.state_trash.events.len();
+    let selected = match app.state_trash.selected {
+        Some(i) if i + 1 < len => Some(i + 1),
+        Some(i) => Some(i),
+        None if len > 0 => Some(0),
+        None => None,
+    };
+    app.state_trash.select(selected);
+    app
+}
+
+pub fn on_key<'a>(c: char, mut app: App<'a>) -> App<'a> {
+    match c {
+        'q' => {
+            app.should_quit = true;
+            app
+        }
+        'j' => on_down(app),
+        'k' => on_up(app),
+        /// Move the selected event back to the calendar
+        'r' => {
+            if let Some(id) = app.state_trash.selected_event().and_then(|e| e.id()) {
+                if let Err(e) = app.store.restore_event(id) {
+                    app.report("Could not restore the event", e);
+                }
+                app.load_trash();
+                app.load_events();
+            }
+            app
+        }
+        /// Delete the selected event for good
+        'p' => {
+            if let Some(id) = app.state_trash.selected_event().and_then(|e| e.id()) {
+                if let Err(e) = app.store.purge_from_trash(id) {
+                    app.report("Could not purge the event", e);
+                }
+                app.load_trash();
+            }
+            app
+        }
+        _ => app,
+    }
+}
@@ -2,24 +2,36 @@
 
 mod app;
 mod calendar;
+mod cli;
 mod event;
 mod files;
 mod keypress;
+mod notify;
 mod popup;
 mod runner;
+mod store;
+mod todo;
 mod ui;
 mod widgets;
 
-use crate::app::App;
+use crate::{
+    app::App,
+    cli::RaydayCli,
+    files::{Config, Files, Paths},
+};
 
 use anyhow::Result;
 use chrono::{Local, NaiveDate};
+use clap::Parser;
 use log2::*;
 use runner::run;
 use std::error::Error;
 
 fn main() -> Result<()> {
-    let _log2 = log2::open("log.txt")
+    let cli = RaydayCli::parse();
+    let paths = Paths::new(cli.db.clone(), cli.config.clone())?;
+
+    let _log2 = log2::open(&paths.log.to_string_lossy())
         .size(100 * 1024 * 1024)
         .rotate(20)
         .tee(false)
@@ -27,11 +39,23 @@ fn main() -> Result<()> {
         .level("debug")
         .start();
 
+    if cli.command.is_some() {
+        return cli.handle_command(&paths);
+    }
+
     let now = Local::now();
     info!("Started Rayday at {} on {}", now.time(), now.date_naive());
 
-    let tick_rate = std::time::Duration::from_secs(5);
-    let mut app = App::new("RayDay", true);
+    let files = Files::new(&paths)?.with_source("tui");
+    // The calendar opens without a snapshot rather than not at all
+    if let Err(e) = files.backup("startup") {
+        error!("Could not back up the database: {}", e);
+    }
+
+    // Changes made by other processes are looked for on every tick
+    let tick_rate = std::time::Duration::from_secs(1);
+    let mut app =
+        App::new("RayDay", true, files, Config::load(&paths.config)?).watch_config(&paths.config);
     let result = run(app, tick_rate, true);
 
     info!("Shutdown with result: {:?}", result);
@@ -0,0 +1,47 @@
+use std::{
+    process::{Command, Stdio},
+    thread,
+};
+
+use anyhow::{anyhow, Result};
+use log2::{error, info};
+
+use crate::event::Event;
+
+/// Message of the reminder about `event`
+pub fn reminder_message(event: &Event) -> String {
+    if event.is_all_day() {
+        format!("{} on {}", event.desc(), event.local_start().date())
+    } else {
+        format!(
+            "{} at {}",
+            event.desc(),
+            event.local_start().format("%H:%M")
+        )
+    }
+}
+
+/// Run the notification `command` with the `message` as its last argument
+pub fn notify(command: &str, message: &str) -> Result<()> {
+    let mut parts = command.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| anyhow!("Notification command is empty"))?;
+
+    // The output would be drawn over the calendar
+    let mut child = Command::new(program)
+        .args(parts)
+        .arg(message)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Wait for the command not to leave a zombie process behind
+    thread::spawn(move || {
+        if let Err(e) = child.wait() {
+            error!("Could not wait for the notification command: {}", e);
+        }
+    });
+    info!("Notified with {}: {}", command, message);
+    Ok(())
+}
@@ -1,39 +1,70 @@
 use std::ops::Add;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use chrono_tz::Tz;
 use rusqlite::ToSql;
 
-use crate::event::Event;
+use crate::event::{
+    format_reminders, format_tags, parse_reminders, parse_tags, rrule::RRule, EditScope, Event,
+    DEFAULT_CALENDAR,
+};
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub enum PopupInputState {
+    Scope,
     StartDate,
     StartTime,
     EndDate,
     EndTime,
+    Recurrence,
+    TimeZone,
+    Reminders,
     Description,
+    Calendar,
+    Tags,
+    Location,
+    Url,
+    Notes,
 }
 
 #[derive(Debug)]
 pub struct PopupInput {
     pub state: PopupInputState,
+    pub scope: Option<EditScope>,
     pub start_date: String,
     pub start_time: String,
     pub end_date: String,
     pub end_time: String,
+    pub recurrence: String,
+    pub timezone: String,
+    pub reminders: String,
     pub description: String,
+    pub calendar: String,
+    pub tags: String,
+    pub location: String,
+    pub url: String,
+    pub notes: String,
 }
 
 impl Default for PopupInput {
     fn default() -> Self {
         PopupInput {
             state: PopupInputState::StartDate,
+            scope: None,
             start_date: String::new(),
             start_time: String::new(),
             end_date: String::new(),
             end_time: String::new(),
+            recurrence: String::new(),
+            timezone: String::new(),
+            reminders: String::new(),
             description: String::new(),
+            calendar: String::new(),
+            tags: String::new(),
+            location: String::new(),
+            url: String::new(),
+            notes: String::new(),
         }
     }
 }
@@ -41,36 +72,111 @@ impl Default for PopupInput {
 impl PopupInput {
     pub fn parse(&self, id: Option<usize>) -> Result<Event> {
         let start_date = NaiveDate::parse_from_str(self.start_date.as_str(), "%Y-%m-%d")
-            .expect("Could not parse start date");
+            .map_err(|_| anyhow!("Could not parse start date {}", self.start_date))?;
         let end_date = NaiveDate::parse_from_str(self.end_date.as_str(), "%Y-%m-%d")
-            .expect("Could not parse end date");
+            .map_err(|_| anyhow!("Could not parse end date {}", self.end_date))?;
+        let rrule = match self.recurrence.trim() {
+            "" => None,
+            r => Some(
+                r.parse::<RRule>()
+                    .map_err(|_| anyhow!("Could not parse recurrence rule {}", r))?,
+            ),
+        };
+
+        let tags = parse_tags(&self.tags);
+        let reminders = parse_reminders(&self.reminders)
+            .map_err(|_| anyhow!("Could not parse reminders {}", self.reminders))?;
+        let optional = |s: &str| match s.trim() {
+            "" => None,
+            s => Some(s.to_string()),
+        };
+
+        let calendar = match self.calendar.trim() {
+            "" => String::from(DEFAULT_CALENDAR),
+            c => c.to_string(),
+        };
+
+        let timezone = match self.timezone.trim() {
+            "" => None,
+            z => Some(
+                z.parse::<Tz>()
+                    .map_err(|_| anyhow!("Unknown time zone {}", z))?,
+            ),
+        };
+
+        // Events without time last all day
+        if self.start_time.is_empty() && self.end_time.is_empty() {
+            return Ok(
+                Event::new_all_day(id, self.description.clone(), start_date, end_date)
+                    .with_rrule(rrule)
+                    .with_calendar(calendar)
+                    .with_tags(tags)
+                    .with_reminders(reminders)
+                    .with_location(optional(&self.location))
+                    .with_notes(optional(&self.notes))
+                    .with_url(optional(&self.url)),
+            );
+        }
+
         let start_time = NaiveTime::parse_from_str(self.start_time.as_str(), "%H:%M:%S")
-            .expect("Could not parse start time");
+            .map_err(|_| anyhow!("Could not parse start time {}", self.start_time))?;
         let end_time = NaiveTime::parse_from_str(self.end_time.as_str(), "%H:%M:%S")
-            .expect("Could not parse end time");
+            .map_err(|_| anyhow!("Could not parse end time {}", self.end_time))?;
 
         Ok(Event::new(
             id,
             self.description.clone(),
             NaiveDateTime::new(start_date, start_time),
             NaiveDateTime::new(end_date, end_time),
-        ))
+        )
+        .with_rrule(rrule)
+        .with_timezone(timezone)
+        .with_calendar(calendar)
+        .with_tags(tags)
+        .with_reminders(reminders)
+        .with_location(optional(&self.location))
+        .with_notes(optional(&self.notes))
+        .with_url(optional(&self.url)))
     }
 
     pub fn load(&mut self, event: &Event) -> Result<()> {
         let start = event.start();
+        let end = event.end();
         self.start_date = start.date().to_string();
-        self.start_time = start.time().to_string();
 
-        let end = event.end();
-        self.end_date = end.date().to_string();
-        self.end_time = end.time().to_string();
+        if event.is_all_day() {
+            // The end of an all-day event is the midnight after its last day
+            self.end_date = (end - Duration::days(1)).date().to_string();
+            self.start_time = String::new();
+            self.end_time = String::new();
+        } else {
+            self.end_date = end.date().to_string();
+            self.start_time = start.time().to_string();
+            self.end_time = end.time().to_string();
+        }
 
+        self.recurrence = event.rrule().map(|r| r.to_string()).unwrap_or_default();
+        self.timezone = event
+            .timezone()
+            .map(|z| z.name().to_string())
+            .unwrap_or_default();
         self.description = event.desc();
+        self.calendar = event.calendar().to_string();
+        self.tags = format_tags(event.tags());
+        self.reminders = format_reminders(event.reminders());
+        self.location = event.location().unwrap_or_default().to_string();
+        self.url = event.url().unwrap_or_default().to_string();
+        self.notes = event.notes().unwrap_or_default().to_string();
 
         Ok(())
     }
 
+    /// Ask for the scope of the changes to a recurring event first
+    pub fn ask_scope(&mut self) {
+        self.scope = Some(EditScope::This);
+        self.state = PopupInputState::Scope;
+    }
+
     pub fn set_date(&mut self, date: NaiveDate) -> Result<()> {
         self.start_date = date.to_string();
         self.end_date = date.to_string();
@@ -1,2 +1,3 @@
 pub mod input;
 pub mod state;
+pub mod todo;
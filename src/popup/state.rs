@@ -1,23 +1,54 @@
 use crate::{app::InputMode, event::Event};
 
-use super::input::PopupInput;
+use super::{input::PopupInput, todo::TodoInput};
 use anyhow::Result;
 
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum PopupAction {
+    Add,
+    Edit,
+    Delete,
+    AddTodo,
+    EditTodo,
+    Goto,
+}
+
 #[derive(Debug)]
 pub struct PopupState {
     pub input: PopupInput,
+    pub todo: TodoInput,
+    /// Id of the event to jump to
+    pub goto: String,
     pub visible: bool,
+    pub action: PopupAction,
 }
 
 impl PopupState {
     pub fn new(input: PopupInput) -> Self {
         Self {
             input,
+            todo: TodoInput::default(),
+            goto: String::new(),
             visible: false,
+            action: PopupAction::Add,
         }
     }
 
     pub fn clear(&mut self) {
         self.input = PopupInput::default();
+        self.todo = TodoInput::default();
+        self.goto.clear();
+        self.action = PopupAction::Add;
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self.action {
+            PopupAction::Add => "Add event",
+            PopupAction::Edit => "Edit event",
+            PopupAction::Delete => "Delete event",
+            PopupAction::AddTodo => "Add todo",
+            PopupAction::EditTodo => "Edit todo",
+            PopupAction::Goto => "Go to event",
+        }
     }
 }
@@ -0,0 +1,66 @@
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+
+use crate::todo::{Todo, DEFAULT_PRIORITY};
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum TodoInputState {
+    Description,
+    Priority,
+    Due,
+}
+
+#[derive(Debug)]
+pub struct TodoInput {
+    pub state: TodoInputState,
+    pub description: String,
+    pub priority: String,
+    pub due: String,
+}
+
+impl Default for TodoInput {
+    fn default() -> Self {
+        TodoInput {
+            state: TodoInputState::Description,
+            description: String::new(),
+            priority: String::new(),
+            due: String::new(),
+        }
+    }
+}
+
+impl TodoInput {
+    pub fn parse(&self, id: Option<usize>, completed: bool) -> Result<Todo> {
+        let priority = match self.priority.trim() {
+            "" => DEFAULT_PRIORITY,
+            p => p
+                .parse::<u8>()
+                .map_err(|_| anyhow!("Could not parse priority {}", p))?,
+        };
+        let due = match self.due.trim() {
+            "" => None,
+            d => Some(
+                NaiveDate::parse_from_str(d, "%Y-%m-%d")
+                    .map_err(|_| anyhow!("Could not parse due date {}", d))?,
+            ),
+        };
+
+        Ok(Todo::new(id, self.description.clone(), priority)
+            .with_due(due)
+            .with_completed(completed))
+    }
+
+    pub fn load(&mut self, todo: &Todo) {
+        self.description = todo.desc();
+        self.priority = todo.priority().to_string();
+        self.due = todo.due().map(|d| d.to_string()).unwrap_or_default();
+    }
+
+    pub fn current_mut(&mut self) -> &mut String {
+        match self.state {
+            TodoInputState::Description => &mut self.description,
+            TodoInputState::Priority => &mut self.priority,
+            TodoInputState::Due => &mut self.due,
+        }
+    }
+}
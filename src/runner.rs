@@ -62,6 +62,7 @@ fn run_app<B: Backend>(
             }
         }
         if last_tick.elapsed() >= tick_rate {
+            app.reload_config();
             app.on_tick();
             last_tick = Instant::now();
         }
@@ -0,0 +1,67 @@
+use std::{fmt::Display, io};
+
+/// Failure of a store to read or write its data
+#[derive(Debug)]
+pub enum StorageError {
+    /// The database could not be opened, read or written, e.g. it is locked or corrupt
+    Database(rusqlite::Error),
+    Io(io::Error),
+    /// The database was written by a newer version of the app
+    UnsupportedVersion {
+        found: u32,
+        supported: u32,
+    },
+    /// The event or todo has no id to be looked up by
+    MissingId,
+    NotFound(usize),
+    /// An event saved in the journal of changes could not be read or written
+    Journal(serde_yaml::Error),
+}
+
+pub type StorageResult<T> = Result<T, StorageError>;
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageError::Database(e) => write!(f, "Database error: {}", e),
+            StorageError::Io(e) => write!(f, "Could not access the database file: {}", e),
+            StorageError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "Database version {} is newer than the supported version {}",
+                found, supported
+            ),
+            StorageError::MissingId => write!(f, "Could not store an entry without an id"),
+            StorageError::NotFound(id) => write!(f, "No entry with id {}", id),
+            StorageError::Journal(e) => write!(f, "Could not read the journal of changes: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StorageError::Database(e) => Some(e),
+            StorageError::Io(e) => Some(e),
+            StorageError::Journal(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(e: rusqlite::Error) -> Self {
+        StorageError::Database(e)
+    }
+}
+
+impl From<io::Error> for StorageError {
+    fn from(e: io::Error) -> Self {
+        StorageError::Io(e)
+    }
+}
+
+impl From<serde_yaml::Error> for StorageError {
+    fn from(e: serde_yaml::Error) -> Self {
+        StorageError::Journal(e)
+    }
+}
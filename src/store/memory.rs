@@ -0,0 +1,645 @@
+use std::collections::BTreeMap;
+
+use chrono::{Local, NaiveDateTime};
+
+use super::{EventStore, StorageError, StorageResult, TodoStore, JOURNAL_LENGTH};
+use crate::{event::Event, todo::Todo};
+
+/// Store keeping everything in memory, for tests and throwaway calendars
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    events: BTreeMap<usize, Event>,
+    todos: BTreeMap<usize, Todo>,
+    last_id: usize,
+    journal: Vec<JournalEntry>,
+    change: Option<usize>,
+    /// Versions of the events replaced by their updates
+    history: Vec<(usize, Event)>,
+    /// Number of the changes of the events
+    version: u64,
+    /// Visibility of the calendars by their names
+    calendars: BTreeMap<String, bool>,
+}
+
+/// Change of an event, `None` before it was added or after it was removed
+#[derive(Debug)]
+struct JournalEntry {
+    change: usize,
+    id: usize,
+    before: Option<Event>,
+    after: Option<Event>,
+    undone: bool,
+    /// Whether `before` was kept in the history, as the updates do
+    history: bool,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        MemoryStore::default()
+    }
+
+    fn next_id(&mut self) -> usize {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    /// Events which are not in the trash
+    fn live_events(&self) -> impl Iterator<Item = &Event> {
+        self.events.values().filter(|e| e.deleted_at().is_none())
+    }
+
+    fn record(&mut self, id: usize, before: Option<Event>, after: Option<Event>) {
+        self.version += 1;
+        let change = match self.change {
+            Some(change) => change,
+            None => {
+                self.journal.retain(|e| !e.undone);
+                let change = self.journal.last().map_or(0, |e| e.change) + 1;
+                let oldest = change.saturating_sub(JOURNAL_LENGTH);
+                self.journal.retain(|e| e.change > oldest);
+                self.change = Some(change);
+                change
+            }
+        };
+        self.journal.push(JournalEntry {
+            change,
+            id,
+            before,
+            after,
+            undone: false,
+            history: false,
+        });
+    }
+
+    fn restore(&mut self, id: usize, event: Option<Event>) {
+        self.version += 1;
+        match event {
+            Some(event) => {
+                self.events.insert(id, event);
+            }
+            None => {
+                self.events.remove(&id);
+                self.history.retain(|(event_id, _)| *event_id != id);
+            }
+        }
+    }
+}
+
+impl EventStore for MemoryStore {
+    fn add_event(&mut self, event: Event) -> StorageResult<usize> {
+        let id = self.next_id();
+        let now = Local::now().naive_local();
+        let event = event
+            .with_id(Some(id))
+            .with_timestamps(Some(now), Some(now), None);
+        self.events.insert(id, event.clone());
+        self.record(id, None, Some(event));
+        Ok(id)
+    }
+
+    fn update_event(&mut self, event: Event) -> StorageResult<()> {
+        let id = event.id().ok_or(StorageError::MissingId)?;
+        let stored = self.events.get_mut(&id).ok_or(StorageError::NotFound(id))?;
+        let event =
+            event.with_timestamps(stored.created_at(), Some(Local::now().naive_local()), None);
+        let before = std::mem::replace(stored, event.clone());
+        self.history.push((id, before.clone()));
+        self.record(id, Some(before), Some(event));
+        if let Some(entry) = self.journal.last_mut() {
+            entry.history = true;
+        }
+        Ok(())
+    }
+
+    fn trash_event(&mut self, id: usize, deleted_at: NaiveDateTime) -> StorageResult<()> {
+        if let Some(stored) = self.events.get_mut(&id) {
+            let deleted = stored
+                .clone()
+                .with_deleted_at(Some(deleted_at))
+                .with_timestamps(stored.created_at(), Some(deleted_at), None);
+            let before = std::mem::replace(stored, deleted.clone());
+            self.record(id, Some(before), Some(deleted));
+        }
+        Ok(())
+    }
+
+    fn purge_event(&mut self, id: usize) -> StorageResult<()> {
+        self.history.retain(|(event_id, _)| *event_id != id);
+        if let Some(before) = self.events.remove(&id) {
+            self.record(id, Some(before), None);
+        }
+        Ok(())
+    }
+
+    fn get_event(&self, id: usize) -> StorageResult<Option<Event>> {
+        Ok(self.live_events().find(|e| e.id() == Some(id)).cloned())
+    }
+
+    fn get_history(&self, id: usize) -> StorageResult<Vec<Event>> {
+        Ok(self
+            .history
+            .iter()
+            .rev()
+            .filter(|(event_id, _)| *event_id == id)
+            .map(|(_, event)| event.clone())
+            .collect())
+    }
+
+    fn get_trash(&self) -> StorageResult<Vec<Event>> {
+        let mut trash: Vec<Event> = self
+            .events
+            .values()
+            .filter(|e| e.deleted_at().is_some())
+            .cloned()
+            .collect();
+        trash.sort_by_key(|e| std::cmp::Reverse(e.deleted_at()));
+        Ok(trash)
+    }
+
+    fn query_events(&self, from: NaiveDateTime, to: NaiveDateTime) -> StorageResult<Vec<Event>> {
+        let mut events: Vec<Event> = self
+            .live_events()
+            .filter(|e| {
+                (e.start() < to && e.end() >= from) || (e.rrule().is_some() && e.start() < to)
+            })
+            .cloned()
+            .collect();
+        events.sort_by_key(|e| e.start());
+        Ok(events)
+    }
+
+    fn get_overrides(&self, master_id: usize) -> StorageResult<Vec<Event>> {
+        Ok(self
+            .live_events()
+            .filter(|e| e.master_id() == Some(master_id))
+            .cloned()
+            .collect())
+    }
+
+    fn search_events(&self, query: &str) -> StorageResult<Vec<Event>> {
+        let query = query.to_lowercase();
+        let terms: Vec<&str> = query.split_whitespace().collect();
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Rank by the number of words matched in the description, then the latest first
+        let mut matches: Vec<(usize, Event)> = self
+            .live_events()
+            .filter_map(|e| {
+                let desc = e.desc().to_lowercase();
+                let notes = e.notes().unwrap_or_default().to_lowercase();
+                let matches = |text: &str, term: &str| {
+                    text.split(|c: char| !c.is_alphanumeric())
+                        .any(|w| w.starts_with(term))
+                };
+                terms
+                    .iter()
+                    .all(|t| matches(&desc, t) || matches(&notes, t))
+                    .then(|| {
+                        (
+                            terms.iter().filter(|t| matches(&desc, t)).count(),
+                            e.clone(),
+                        )
+                    })
+            })
+            .collect();
+        matches.sort_by_key(|(in_desc, e)| {
+            (std::cmp::Reverse(*in_desc), std::cmp::Reverse(e.start()))
+        });
+        Ok(matches.into_iter().map(|(_, e)| e).collect())
+    }
+
+    fn data_version(&self) -> StorageResult<u64> {
+        Ok(self.version)
+    }
+
+    fn end_change(&mut self) {
+        self.change = None;
+    }
+
+    fn get_calendar_visibility(&self) -> StorageResult<Vec<(String, bool)>> {
+        Ok(self.calendars.clone().into_iter().collect())
+    }
+
+    fn set_calendar_visible(&mut self, name: &str, visible: bool) -> StorageResult<()> {
+        self.calendars.insert(String::from(name), visible);
+        self.version += 1;
+        Ok(())
+    }
+
+    fn undo(&mut self) -> StorageResult<bool> {
+        self.change = None;
+        let change = match self.journal.iter().rev().find(|e| !e.undone) {
+            Some(entry) => entry.change,
+            None => return Ok(false),
+        };
+        let mut restored = Vec::new();
+        for entry in self.journal.iter_mut().rev().filter(|e| e.change == change) {
+            entry.undone = true;
+            restored.push((entry.id, entry.before.clone(), entry.history));
+        }
+        for (id, event, history) in restored {
+            // The version replaced by an undone update leaves the history
+            if let Some(i) = self.history.iter().rposition(|(h, _)| history && *h == id) {
+                self.history.remove(i);
+            }
+            self.restore(id, event);
+        }
+        Ok(true)
+    }
+
+    fn redo(&mut self) -> StorageResult<bool> {
+        self.change = None;
+        let change = match self.journal.iter().find(|e| e.undone) {
+            Some(entry) => entry.change,
+            None => return Ok(false),
+        };
+        let mut restored = Vec::new();
+        for entry in self.journal.iter_mut().filter(|e| e.change == change) {
+            entry.undone = false;
+            let replaced = entry.before.clone().filter(|_| entry.history);
+            restored.push((entry.id, entry.after.clone(), replaced));
+        }
+        for (id, event, replaced) in restored {
+            if let Some(replaced) = replaced {
+                self.history.push((id, replaced));
+            }
+            self.restore(id, event);
+        }
+        Ok(true)
+    }
+}
+
+impl TodoStore for MemoryStore {
+    fn add_todo(&mut self, todo: Todo) -> StorageResult<usize> {
+        let id = self.next_id();
+        self.todos.insert(id, todo.with_id(Some(id)));
+        Ok(id)
+    }
+
+    fn update_todo(&mut self, todo: Todo) -> StorageResult<()> {
+        let id = todo.id().ok_or(StorageError::MissingId)?;
+        let stored = self.todos.get_mut(&id).ok_or(StorageError::NotFound(id))?;
+        *stored = todo;
+        Ok(())
+    }
+
+    fn remove_todo(&mut self, id: usize) -> StorageResult<()> {
+        self.todos.remove(&id);
+        Ok(())
+    }
+
+    fn get_todos(&self) -> StorageResult<Vec<Todo>> {
+        let mut todos: Vec<Todo> = self.todos.values().cloned().collect();
+        todos.sort_by_key(|t| {
+            (
+                t.is_completed(),
+                t.priority(),
+                t.due().is_none(),
+                t.due(),
+                t.id(),
+            )
+        });
+        Ok(todos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{rrule::RRule, EditScope};
+    use chrono::{Datelike, NaiveDate};
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2023, 7, d).unwrap()
+    }
+
+    fn weekly_standup() -> Event {
+        Event::new(
+            None,
+            String::from("Standup"),
+            date(3).and_hms_opt(10, 0, 0).unwrap(),
+            date(3).and_hms_opt(10, 15, 0).unwrap(),
+        )
+        .with_rrule("weekly".parse::<RRule>().ok())
+    }
+
+    #[test]
+    fn add_update_remove() {
+        let mut store = MemoryStore::new();
+        let id = store.add_event(weekly_standup()).unwrap();
+
+        let event = store.get_event(id).unwrap().unwrap();
+        store
+            .update_event(event.with_location(Some(String::from("Room 1"))))
+            .unwrap();
+        assert_eq!(
+            store.get_event(id).unwrap().unwrap().location(),
+            Some("Room 1")
+        );
+
+        store.remove_event(id).unwrap();
+        assert_eq!(store.get_event(id).unwrap(), None);
+    }
+
+    #[test]
+    fn events_between() {
+        let mut store = MemoryStore::new();
+        store.add_event(weekly_standup()).unwrap();
+        store
+            .add_event(Event::new(
+                None,
+                String::from("Conference"),
+                date(11).and_hms_opt(9, 0, 0).unwrap(),
+                date(13).and_hms_opt(18, 0, 0).unwrap(),
+            ))
+            .unwrap();
+
+        let events = store
+            .get_events_between(
+                date(10).and_hms_opt(10, 15, 0).unwrap(),
+                date(17).and_hms_opt(10, 0, 0).unwrap(),
+            )
+            .unwrap();
+        let descs: Vec<String> = events.iter().map(|e| e.desc()).collect();
+        assert_eq!(descs, vec!["Conference"]);
+
+        let events = store
+            .get_events_between(
+                date(1).and_hms_opt(0, 0, 0).unwrap(),
+                date(18).and_hms_opt(0, 0, 0).unwrap(),
+            )
+            .unwrap();
+        let starts: Vec<u32> = events.iter().map(|e| e.start().day()).collect();
+        assert_eq!(starts, vec![3, 10, 11, 17]);
+        assert_eq!(
+            store.get_event_dates(date(9), date(15)).unwrap(),
+            vec![date(10), date(11), date(12), date(13)]
+        );
+    }
+
+    #[test]
+    fn update_missing_event() {
+        let mut store = MemoryStore::new();
+        assert!(matches!(
+            store.update_event(weekly_standup()),
+            Err(StorageError::MissingId)
+        ));
+        assert!(matches!(
+            store.update_event(weekly_standup().with_id(Some(7))),
+            Err(StorageError::NotFound(7))
+        ));
+    }
+
+    #[test]
+    fn edit_one_occurrence() {
+        let mut store = MemoryStore::new();
+        store.add_event(weekly_standup()).unwrap();
+
+        let occurrence = store.get_events_on_date(date(10)).unwrap().remove(0);
+        let moved = Event::new(
+            occurrence.id(),
+            occurrence.desc(),
+            date(10).and_hms_opt(11, 0, 0).unwrap(),
+            date(10).and_hms_opt(11, 15, 0).unwrap(),
+        );
+        store
+            .update_event_in_scope(&occurrence, moved, EditScope::This)
+            .unwrap();
+
+        let events = store.get_events_on_date(date(10)).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].start(), date(10).and_hms_opt(11, 0, 0).unwrap());
+        assert_eq!(store.get_events_on_date(date(17)).unwrap().len(), 1);
+        assert_eq!(
+            store.get_event_dates(date(1), date(18)).unwrap(),
+            vec![date(3), date(10), date(17)]
+        );
+    }
+
+    #[test]
+    fn undo_edit_of_following_occurrences() {
+        let mut store = MemoryStore::new();
+        store.add_event(weekly_standup()).unwrap();
+        store.end_change();
+
+        let occurrence = store.get_events_on_date(date(17)).unwrap().remove(0);
+        let renamed = Event::new(
+            occurrence.id(),
+            String::from("Sync"),
+            occurrence.start(),
+            occurrence.end(),
+        )
+        .with_rrule(occurrence.rrule().cloned());
+        store
+            .update_event_in_scope(&occurrence, renamed, EditScope::Following)
+            .unwrap();
+        store.end_change();
+        assert_eq!(
+            store.get_events_on_date(date(24)).unwrap()[0].desc(),
+            "Sync"
+        );
+
+        // Both the ended and the continued series are reverted at once
+        assert!(store.undo().unwrap());
+        assert_eq!(store.events.len(), 1);
+        assert_eq!(
+            store.get_events_on_date(date(24)).unwrap()[0].desc(),
+            "Standup"
+        );
+
+        assert!(store.redo().unwrap());
+        assert_eq!(
+            store.get_events_on_date(date(10)).unwrap()[0].desc(),
+            "Standup"
+        );
+        assert_eq!(
+            store.get_events_on_date(date(24)).unwrap()[0].desc(),
+            "Sync"
+        );
+    }
+
+    #[test]
+    fn updates_keep_history() {
+        let mut store = MemoryStore::new();
+        let id = store.add_event(weekly_standup()).unwrap();
+        let created_at = store.get_event(id).unwrap().unwrap().created_at();
+        assert!(created_at.is_some());
+
+        let event = store.get_event(id).unwrap().unwrap();
+        store
+            .update_event(event.with_location(Some(String::from("Room 1"))))
+            .unwrap();
+        let event = store.get_event(id).unwrap().unwrap();
+        store
+            .update_event(event.with_location(Some(String::from("Room 2"))))
+            .unwrap();
+
+        let event = store.get_event(id).unwrap().unwrap();
+        assert_eq!(event.created_at(), created_at);
+        assert!(event.updated_at() >= created_at);
+        let locations: Vec<Option<String>> = store
+            .get_history(id)
+            .unwrap()
+            .iter()
+            .map(|e| e.location().map(String::from))
+            .collect();
+        assert_eq!(locations, vec![Some(String::from("Room 1")), None]);
+    }
+
+    #[test]
+    fn undone_updates_leave_the_history() {
+        let mut store = MemoryStore::new();
+        let id = store.add_event(weekly_standup()).unwrap();
+        store.end_change();
+        let event = store.get_event(id).unwrap().unwrap();
+        store
+            .update_event(event.with_location(Some(String::from("Room 1"))))
+            .unwrap();
+
+        assert!(store.undo().unwrap());
+        assert!(store.get_history(id).unwrap().is_empty());
+        assert!(store.redo().unwrap());
+        assert_eq!(store.get_history(id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn trash_restore_and_purge() {
+        let mut store = MemoryStore::new();
+        let id = store.add_event(weekly_standup()).unwrap();
+        let occurrence = store.get_events_on_date(date(10)).unwrap().remove(0);
+        let moved = Event::new(
+            occurrence.id(),
+            occurrence.desc(),
+            date(10).and_hms_opt(11, 0, 0).unwrap(),
+            date(10).and_hms_opt(11, 15, 0).unwrap(),
+        );
+        store
+            .update_event_in_scope(&occurrence, moved, EditScope::This)
+            .unwrap();
+
+        store
+            .remove_event_in_scope(&occurrence, EditScope::All)
+            .unwrap();
+        assert!(store.get_events_on_date(date(10)).unwrap().is_empty());
+        assert_eq!(store.get_event(id).unwrap(), None);
+        assert_eq!(store.get_trash().unwrap().len(), 2);
+
+        // The override comes back with its recurring event
+        store.restore_event(id).unwrap();
+        assert!(store.get_trash().unwrap().is_empty());
+        let events = store.get_events_on_date(date(10)).unwrap();
+        assert_eq!(events[0].start(), date(10).and_hms_opt(11, 0, 0).unwrap());
+
+        store.remove_event(id).unwrap();
+        let deleted_at = store.get_trash().unwrap()[0].deleted_at().unwrap();
+        assert_eq!(store.purge_trash(deleted_at).unwrap(), 0);
+        assert_eq!(
+            store
+                .purge_trash(deleted_at + chrono::Duration::seconds(1))
+                .unwrap(),
+            1
+        );
+        assert!(store.get_trash().unwrap().is_empty());
+        assert_eq!(store.events.len(), 1);
+    }
+
+    #[test]
+    fn editing_all_from_a_moved_override_keeps_the_times() {
+        let mut store = MemoryStore::new();
+        let id = store.add_event(weekly_standup()).unwrap();
+        let occurrence = store.get_events_on_date(date(10)).unwrap().remove(0);
+        let moved = Event::new(
+            occurrence.id(),
+            occurrence.desc(),
+            date(10).and_hms_opt(11, 0, 0).unwrap(),
+            date(10).and_hms_opt(11, 15, 0).unwrap(),
+        );
+        store
+            .update_event_in_scope(&occurrence, moved, EditScope::This)
+            .unwrap();
+
+        let moved = store.get_events_on_date(date(10)).unwrap().remove(0);
+        let renamed = Event::new(
+            moved.id(),
+            String::from("Daily"),
+            moved.start(),
+            moved.end(),
+        );
+        store
+            .update_event_in_scope(&moved, renamed, EditScope::All)
+            .unwrap();
+
+        let master = store.get_event(id).unwrap().unwrap();
+        assert_eq!(master.desc(), "Daily");
+        assert_eq!(master.start(), date(3).and_hms_opt(10, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn editing_all_occurrences_keeps_the_calendar() {
+        let mut store = MemoryStore::new();
+        let id = store
+            .add_event(weekly_standup().with_calendar(String::from("work")))
+            .unwrap();
+        let occurrence = store.get_events_on_date(date(10)).unwrap().remove(0);
+        let edited = Event::new(
+            occurrence.id(),
+            String::from("Daily"),
+            occurrence.start(),
+            occurrence.end(),
+        )
+        .with_calendar(String::from("work"));
+        store
+            .update_event_in_scope(&occurrence, edited, EditScope::All)
+            .unwrap();
+
+        let master = store.get_event(id).unwrap().unwrap();
+        assert_eq!(master.desc(), "Daily");
+        assert_eq!(master.calendar(), "work");
+    }
+
+    #[test]
+    fn overrides_trashed_before_stay_in_the_trash() {
+        let mut store = MemoryStore::new();
+        let id = store.add_event(weekly_standup()).unwrap();
+        let occurrence = store.get_events_on_date(date(10)).unwrap().remove(0);
+        let moved = Event::new(
+            occurrence.id(),
+            occurrence.desc(),
+            date(10).and_hms_opt(11, 0, 0).unwrap(),
+            date(10).and_hms_opt(11, 15, 0).unwrap(),
+        );
+        store
+            .update_event_in_scope(&occurrence, moved, EditScope::This)
+            .unwrap();
+        let moved = store.get_events_on_date(date(10)).unwrap().remove(0);
+        store
+            .remove_event_in_scope(&moved, EditScope::This)
+            .unwrap();
+
+        let occurrence = store.get_events_on_date(date(17)).unwrap().remove(0);
+        store
+            .remove_event_in_scope(&occurrence, EditScope::All)
+            .unwrap();
+        store.restore_event(id).unwrap();
+        assert!(store.get_events_on_date(date(10)).unwrap().is_empty());
+        assert_eq!(store.get_events_on_date(date(17)).unwrap().len(), 1);
+        assert_eq!(store.get_trash().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn remove_following_occurrences() {
+        let mut store = MemoryStore::new();
+        store.add_event(weekly_standup()).unwrap();
+
+        let occurrence = store.get_events_on_date(date(17)).unwrap().remove(0);
+        store
+            .remove_event_in_scope(&occurrence, EditScope::Following)
+            .unwrap();
+
+        assert_eq!(store.get_events_on_date(date(10)).unwrap().len(), 1);
+        assert!(store.get_events_on_date(date(17)).unwrap().is_empty());
+        assert!(store.get_events_on_date(date(24)).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,375 @@
+mod error;
+pub mod memory;
+
+pub use error::{StorageError, StorageResult};
+
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime};
+
+use crate::{
+    event::{EditScope, Event, MAX_REMINDER_MINUTES},
+    todo::Todo,
+};
+
+/// Number of the last changes kept to be undone
+pub const JOURNAL_LENGTH: usize = 100;
+
+/// Storage of the events
+pub trait EventStore {
+    /// Save a new event and return its id
+    fn add_event(&mut self, event: Event) -> StorageResult<usize>;
+
+    fn update_event(&mut self, event: Event) -> StorageResult<()>;
+
+    /// Move the event to the trash at `deleted_at`, it is left out of the
+    /// queries until restored
+    fn trash_event(&mut self, id: usize, deleted_at: NaiveDateTime) -> StorageResult<()>;
+
+    /// Delete the event for good
+    fn purge_event(&mut self, id: usize) -> StorageResult<()>;
+
+    /// Get the event unless it is in the trash
+    fn get_event(&self, id: usize) -> StorageResult<Option<Event>>;
+
+    /// Get the events in the trash, the last deleted first
+    fn get_trash(&self) -> StorageResult<Vec<Event>>;
+
+    /// Get the versions of the event replaced by its updates, the latest first
+    fn get_history(&self, id: usize) -> StorageResult<Vec<Event>>;
+
+    /// Get the stored events overlapping `[from, to)`, those ending at `from`
+    /// included, and all recurring events started before `to`
+    fn query_events(&self, from: NaiveDateTime, to: NaiveDateTime) -> StorageResult<Vec<Event>>;
+
+    /// Get the overrides of the occurrences of a recurring event
+    fn get_overrides(&self, master_id: usize) -> StorageResult<Vec<Event>>;
+
+    /// Find the events whose description or notes contain all words of `query`
+    /// as prefixes of their words, the best matches first
+    fn search_events(&self, query: &str) -> StorageResult<Vec<Event>>;
+
+    /// Number which changes whenever the stored events change,
+    /// by this store or by another process sharing the storage
+    fn data_version(&self) -> StorageResult<u64>;
+
+    /// Close the change being recorded in the journal, the changes
+    /// made before are undone separately from the ones made after
+    fn end_change(&mut self);
+
+    /// Revert the last change which is not undone, return if there was one
+    fn undo(&mut self) -> StorageResult<bool>;
+
+    /// Make the last undone change again, return if there was one
+    fn redo(&mut self) -> StorageResult<bool>;
+
+    /// Get the calendars shown or hidden since, by their names
+    fn get_calendar_visibility(&self) -> StorageResult<Vec<(String, bool)>>;
+
+    /// Show or hide the events of the calendar named `name`
+    fn set_calendar_visible(&mut self, name: &str, visible: bool) -> StorageResult<()>;
+
+    /// Get the events and occurrences of recurring events overlapping
+    /// `[from, to)` of the local time, events without duration starting at `from` included
+    fn get_events_between(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> StorageResult<Vec<Event>> {
+        // The range is widened by a day to find events in other time zones
+        let events = self.query_events(from - Duration::days(1), to + Duration::days(1))?;
+
+        // Expand recurring events into their occurrences in the range
+        let last_date = (to - Duration::nanoseconds(1)).date();
+        let mut events: Vec<Event> = events
+            .into_iter()
+            .flat_map(|e| e.occurrences_between(from.date(), last_date.succ_opt().unwrap()))
+            .filter(|e| e.overlaps(from, to))
+            .collect();
+
+        events.sort_by_key(|e| (e.local_start(), !e.is_all_day(), e.local_end()));
+        Ok(events)
+    }
+
+    fn get_events_on_date(&self, date: NaiveDate) -> StorageResult<Vec<Event>> {
+        let day_start = date.and_hms_opt(0, 0, 0).unwrap();
+        let mut events = self.get_events_between(day_start, day_start + Duration::days(1))?;
+
+        // All-day events go first to be shown above the time grid
+        events.sort_by_key(|e| (!e.is_all_day(), e.local_start(), e.local_end()));
+        Ok(events)
+    }
+
+    /// Get the dates in range `[from, to)` which have at least one event
+    fn get_event_dates(&self, from: NaiveDate, to: NaiveDate) -> StorageResult<Vec<NaiveDate>> {
+        self.get_dates_with(from, to, &|_| true)
+    }
+
+    /// Get the dates in range `[from, to)` which have at least one all-day event
+    fn get_all_day_dates(&self, from: NaiveDate, to: NaiveDate) -> StorageResult<Vec<NaiveDate>> {
+        self.get_dates_with(from, to, &|e| e.is_all_day())
+    }
+
+    /// Get the dates in range `[from, to)` which have at least one event matching `f`
+    fn get_dates_with(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        f: &dyn Fn(&Event) -> bool,
+    ) -> StorageResult<Vec<NaiveDate>> {
+        if from >= to {
+            return Ok(Vec::new());
+        }
+        let events: Vec<Event> = self
+            .get_events_between(
+                from.and_hms_opt(0, 0, 0).unwrap(),
+                to.and_hms_opt(0, 0, 0).unwrap(),
+            )?
+            .into_iter()
+            .filter(|e| f(e))
+            .collect();
+
+        Ok(from
+            .iter_days()
+            .take_while(|date| *date < to)
+            .filter(|date| {
+                let day_start = date.and_hms_opt(0, 0, 0).unwrap();
+                let day_end = day_start + Duration::days(1);
+                events.iter().any(|e| e.overlaps(day_start, day_end))
+            })
+            .collect())
+    }
+
+    /// Get the reminders going off after `from` until `to` inclusive
+    /// with the events they remind of
+    fn get_reminders_between(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> StorageResult<Vec<(NaiveDateTime, Event)>> {
+        let last = to + Duration::minutes(MAX_REMINDER_MINUTES as i64);
+        let mut reminders = Vec::new();
+        for event in self.get_events_between(from, last + Duration::seconds(1))? {
+            for time in event.reminder_times() {
+                if from < time && time <= to {
+                    reminders.push((time, event.clone()));
+                }
+            }
+        }
+        reminders.sort_by_key(|(time, _)| *time);
+        Ok(reminders)
+    }
+
+    /// Update the occurrences of a recurring event in `scope`,
+    /// `occurrence` is the selected occurrence before editing
+    fn update_event_in_scope(
+        &mut self,
+        occurrence: &Event,
+        event: Event,
+        scope: EditScope,
+    ) -> StorageResult<()> {
+        let date = occurrence.occurrence_date();
+        // Overrides stay overrides unless the whole series changes
+        let event = event.with_master(occurrence.master_id(), occurrence.recurrence_id());
+        let master_id = match occurrence.master_id().or(occurrence.id()) {
+            Some(id) => id,
+            None => return self.add_event(event).map(|_| ()),
+        };
+
+        match scope {
+            EditScope::This if occurrence.master_id().is_some() => self.update_event(event),
+            EditScope::This => {
+                let mut master = self.get_master(master_id)?;
+                master.exdates_mut().push(date);
+                self.update_event(master)?;
+                self.add_event(
+                    event
+                        .with_id(None)
+                        .with_rrule(None)
+                        .with_exdates(Vec::new())
+                        .with_master(Some(master_id), Some(date)),
+                )?;
+                Ok(())
+            }
+            EditScope::Following => {
+                let master = self.get_master(master_id)?;
+                if date <= master.start().date() {
+                    return self.update_event_in_scope(occurrence, event, EditScope::All);
+                }
+                let rrule = match master.rrule() {
+                    Some(rrule) => rrule.clone(),
+                    None => return self.update_event(event),
+                };
+
+                // Continue the series from the occurrence with the edited event
+                let continued = match event.rrule() {
+                    Some(r) if *r == rrule => Some(r.continued_from(master.start(), date)),
+                    r => r.cloned(),
+                };
+                let exdates = master.exdates().iter().filter(|d| **d >= date).copied();
+                let continued_id = self.add_event(
+                    event
+                        .with_id(None)
+                        .with_master(None, None)
+                        .with_rrule(continued)
+                        .with_exdates(exdates.collect()),
+                )?;
+
+                // Move the overrides of the following occurrences to the continued series
+                for o in self.get_overrides(master_id)? {
+                    if o.recurrence_id().is_some_and(|d| d >= date) {
+                        let recurrence_id = o.recurrence_id();
+                        self.update_event(o.with_master(Some(continued_id), recurrence_id))?;
+                    }
+                }
+
+                self.end_series_before(master, date)
+            }
+            EditScope::All => {
+                let master = self.get_master(master_id)?;
+                // Moving an occurrence moves the others as much
+                let start = master.start() + (event.start() - occurrence.start());
+                let end = start + (event.end() - event.start());
+                let rrule = event.rrule().or(master.rrule()).cloned();
+
+                self.update_event(
+                    Event::new(Some(master_id), event.desc(), start, end)
+                        .with_all_day(event.is_all_day())
+                        .with_timezone(event.timezone())
+                        .with_tags(event.tags().to_vec())
+                        .with_location(event.location().map(String::from))
+                        .with_notes(event.notes().map(String::from))
+                        .with_url(event.url().map(String::from))
+                        .with_calendar(event.calendar().to_string())
+                        .with_reminders(event.reminders().to_vec())
+                        .with_rrule(rrule)
+                        .with_exdates(master.exdates().to_vec()),
+                )
+            }
+        }
+    }
+
+    /// Remove the occurrences of a recurring event in `scope`
+    fn remove_event_in_scope(&mut self, occurrence: &Event, scope: EditScope) -> StorageResult<()> {
+        let date = occurrence.occurrence_date();
+        let master_id = match (occurrence.master_id(), occurrence.id()) {
+            (Some(master_id), _) => master_id,
+            (None, Some(id)) => id,
+            (None, None) => return Ok(()),
+        };
+
+        match scope {
+            EditScope::This => {
+                if occurrence.master_id().is_some() {
+                    self.remove_event(occurrence.id().unwrap())
+                } else {
+                    let mut master = self.get_master(master_id)?;
+                    master.exdates_mut().push(date);
+                    self.update_event(master)
+                }
+            }
+            EditScope::Following => {
+                let master = self.get_master(master_id)?;
+                if date <= master.start().date() {
+                    return self.remove_event_in_scope(occurrence, EditScope::All);
+                }
+                for o in self.get_overrides(master_id)? {
+                    if o.recurrence_id().is_some_and(|d| d >= date) {
+                        self.remove_event(o.id().unwrap())?;
+                    }
+                }
+                self.end_series_before(master, date)
+            }
+            EditScope::All => {
+                // Trashed together so that they are restored together
+                let deleted_at = Local::now().naive_local();
+                for o in self.get_overrides(master_id)? {
+                    self.trash_event(o.id().unwrap(), deleted_at)?;
+                }
+                self.trash_event(master_id, deleted_at)
+            }
+        }
+    }
+
+    /// End the series of a recurring event right before `date`
+    fn end_series_before(&mut self, master: Event, date: NaiveDate) -> StorageResult<()> {
+        let rrule = master
+            .rrule()
+            .map(|rrule| rrule.ended_before(master.start(), date));
+        let exdates = master
+            .exdates()
+            .iter()
+            .filter(|d| **d < date)
+            .copied()
+            .collect();
+        self.update_event(master.with_rrule(rrule).with_exdates(exdates))
+    }
+
+    /// Move the event to the trash, it is left out of the queries until restored
+    fn remove_event(&mut self, id: usize) -> StorageResult<()> {
+        self.trash_event(id, Local::now().naive_local())
+    }
+
+    /// Get the event in the trash and the overrides trashed along with it
+    fn get_trashed_with(&self, id: usize) -> StorageResult<Vec<Event>> {
+        let trash = self.get_trash()?;
+        let deleted_at = match trash.iter().find(|e| e.id() == Some(id)) {
+            Some(event) => event.deleted_at(),
+            None => return Ok(vec![]),
+        };
+        Ok(trash
+            .into_iter()
+            .filter(|e| {
+                e.id() == Some(id) || (e.master_id() == Some(id) && e.deleted_at() == deleted_at)
+            })
+            .collect())
+    }
+
+    /// Move the event and the overrides trashed with it back from the trash
+    fn restore_event(&mut self, id: usize) -> StorageResult<()> {
+        for event in self.get_trashed_with(id)? {
+            self.update_event(event.with_deleted_at(None))?;
+        }
+        Ok(())
+    }
+
+    /// Delete the event in the trash and the overrides trashed with it for good
+    fn purge_from_trash(&mut self, id: usize) -> StorageResult<()> {
+        for event in self.get_trashed_with(id)? {
+            self.purge_event(event.id().unwrap())?;
+        }
+        Ok(())
+    }
+
+    /// Delete for good the events moved to the trash before `time`,
+    /// return how many of them were deleted
+    fn purge_trash(&mut self, time: NaiveDateTime) -> StorageResult<usize> {
+        let mut purged = 0;
+        for event in self.get_trash()? {
+            if event.deleted_at().is_some_and(|t| t < time) {
+                self.purge_event(event.id().unwrap())?;
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+
+    fn get_master(&self, id: usize) -> StorageResult<Event> {
+        self.get_event(id)?.ok_or(StorageError::NotFound(id))
+    }
+}
+
+/// Storage of the todos
+pub trait TodoStore {
+    fn add_todo(&mut self, todo: Todo) -> StorageResult<usize>;
+
+    fn update_todo(&mut self, todo: Todo) -> StorageResult<()>;
+
+    fn remove_todo(&mut self, id: usize) -> StorageResult<()>;
+
+    /// Get todos, uncompleted first, by priority and due date
+    fn get_todos(&self) -> StorageResult<Vec<Todo>>;
+}
+
+/// Storage of everything the app keeps
+pub trait Store: EventStore + TodoStore {}
+
+impl<S: EventStore + TodoStore> Store for S {}
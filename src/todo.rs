@@ -0,0 +1,120 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+pub const HIGHEST_PRIORITY: u8 = 1;
+pub const LOWEST_PRIORITY: u8 = 9;
+pub const DEFAULT_PRIORITY: u8 = 5;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Todo {
+    id: Option<usize>,
+    description: String,
+    priority: u8,
+    due: Option<NaiveDate>,
+    completed: bool,
+}
+
+impl Todo {
+    /// Create a todo, the priority is from 1 (highest) to 9 (lowest)
+    pub fn new(id: Option<usize>, description: String, priority: u8) -> Todo {
+        Todo {
+            id,
+            description,
+            priority: priority.clamp(HIGHEST_PRIORITY, LOWEST_PRIORITY),
+            due: None,
+            completed: false,
+        }
+    }
+
+    pub fn with_id(mut self, id: Option<usize>) -> Todo {
+        self.id = id;
+        self
+    }
+
+    pub fn with_due(mut self, due: Option<NaiveDate>) -> Todo {
+        self.due = due;
+        self
+    }
+
+    pub fn with_completed(mut self, completed: bool) -> Todo {
+        self.completed = completed;
+        self
+    }
+
+    pub fn id(&self) -> Option<usize> {
+        self.id
+    }
+
+    pub fn desc(&self) -> String {
+        self.description.to_string()
+    }
+
+    pub fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    pub fn due(&self) -> Option<NaiveDate> {
+        self.due
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.completed
+    }
+
+    pub fn toggle_completed(&mut self) {
+        self.completed = !self.completed;
+    }
+
+    pub fn raise_priority(&mut self) {
+        self.priority = self.priority.saturating_sub(1).max(HIGHEST_PRIORITY);
+    }
+
+    pub fn lower_priority(&mut self) {
+        self.priority = (self.priority + 1).min(LOWEST_PRIORITY);
+    }
+
+    /// Check if the todo is not completed after its due date
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        !self.completed && self.due.is_some_and(|due| due < today)
+    }
+}
+
+impl Display for Todo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{:?}|{}|{}|{:?}|{}",
+            self.id, self.priority, self.description, self.due, self.completed
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn priority_bounds() {
+        let mut todo = Todo::new(None, String::from("Taxes"), 0);
+        assert_eq!(todo.priority(), HIGHEST_PRIORITY);
+
+        todo.raise_priority();
+        assert_eq!(todo.priority(), HIGHEST_PRIORITY);
+
+        for _ in 0..20 {
+            todo.lower_priority();
+        }
+        assert_eq!(todo.priority(), LOWEST_PRIORITY);
+    }
+
+    #[test]
+    fn overdue_todo() {
+        let today = NaiveDate::from_ymd_opt(2023, 7, 18).unwrap();
+        let mut todo = Todo::new(None, String::from("Report"), DEFAULT_PRIORITY)
+            .with_due(NaiveDate::from_ymd_opt(2023, 7, 17));
+
+        assert!(todo.is_overdue(today));
+        todo.toggle_completed();
+        assert!(!todo.is_overdue(today));
+    }
+}
@@ -1,13 +1,19 @@
 use std::ops::Div;
 
 use crate::{
-    app::{App, InputMode},
+    app::{Alert, App, InputMode, CALENDAR_TAB, TODO_TAB, TRASH_TAB},
+    popup::state::PopupAction,
     widgets::{
         calendar::CalendarWidget,
+        event_details::EventDetailsWidget,
+        event_history::EventHistoryWidget,
         event_view::{EventViewState, EventViewWidget},
         grid::Grid,
-        popup::{self, centered_rect, PopupWidget},
+        popup::{self, centered_rect, PopupWidget, TodoPopupWidget},
+        search_view::SearchViewWidget,
         time_grid::TimeGrid,
+        todo_view::TodoViewWidget,
+        trash_view::TrashViewWidget,
         weeks::Weeks,
     },
 };
@@ -48,10 +54,60 @@ pub(crate) fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
     f.render_widget(tabs, chunks[0]);
 
     match app.state_tabs.index {
-        0 => draw_first_tab(f, app, chunks[1]),
-        1 => draw_second_tab(f, app, chunks[1]),
+        CALENDAR_TAB => draw_first_tab(f, app, chunks[1]),
+        TODO_TAB => draw_second_tab(f, app, chunks[1]),
+        TRASH_TAB => draw_trash_tab(f, app, chunks[1]),
         _ => {}
     };
+
+    if let Some(alert) = &app.alert {
+        draw_alert(f, alert, chunks[1]);
+    }
+}
+
+fn draw_alert<B>(f: &mut Frame<B>, alert: &Alert, area: Rect)
+where
+    B: Backend,
+{
+    let height = alert.message.lines().count() as u16 + 2;
+    let alert_area = centered_rect(height, popup::WIDTH, area);
+    let paragraph = Paragraph::new(alert.message.as_str())
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .title(format!("{} (press any key)", alert.title))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Red))
+                .border_type(BorderType::Rounded)
+                .style(Style::default().bg(Color::Black)),
+        );
+    f.render_widget(Clear, alert_area);
+    f.render_widget(paragraph, alert_area);
+}
+
+/// List of the calendars with their numbers to toggle them,
+/// the hidden ones are crossed out
+fn draw_calendars<B>(f: &mut Frame<B>, app: &App, area: Rect)
+where
+    B: Backend,
+{
+    let items: Vec<ListItem> = app
+        .config
+        .calendars
+        .iter()
+        .enumerate()
+        .map(|(i, calendar)| {
+            let style = match calendar.visible {
+                true => Style::default().fg(calendar.color),
+                false => Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::CROSSED_OUT),
+            };
+            ListItem::new(Span::styled(format!("{} {}", i + 1, calendar.name), style))
+        })
+        .collect();
+    let list = List::new(items).block(Block::default().borders(Borders::ALL).title("Calendars"));
+    f.render_widget(list, area);
 }
 
 fn draw_first_tab<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
@@ -67,23 +123,48 @@ where
 
     let info_style = Style::default().fg(Color::Blue);
 
-    let height_without_borders = chunks[0].height - 2;
+    // The calendars are listed under the weeks
+    let sidebar = Layout::default()
+        .constraints(
+            [
+                Constraint::Min(6),
+                Constraint::Length(app.config.calendars.len() as u16 + 2),
+            ]
+            .as_ref(),
+        )
+        .split(chunks[0]);
+    draw_calendars(f, app, sidebar[1]);
+
+    let height_without_borders = sidebar[0].height - 2;
+    let first_date = Weeks::get_curr_date(
+        app.state_calendar.get_selected_date(),
+        height_without_borders,
+        sidebar[0].width,
+    );
+    let last_date = first_date + Duration::weeks(height_without_borders.into());
+    app.load_marked_dates(first_date, last_date);
+    let (event_dates, all_day_dates) = match &app.marked_dates {
+        Some(dates) => (dates.events.as_slice(), dates.all_day.as_slice()),
+        None => (&[][..], &[][..]),
+    };
     let weeks = Weeks::new(
         app.state_calendar.get_selected_date(),
         height_without_borders,
-        chunks[0].width,
+        sidebar[0].width,
+        event_dates,
+        all_day_dates,
     );
     let mut calendar = CalendarWidget::default()
         .with_weeks(weeks)
         .style(&app.input_mode.current().unwrap())
-        .highlight_style(app.files.get_config().highlight_color);
-    f.render_stateful_widget(calendar, chunks[0], &mut app.state_calendar);
+        .highlight_style(app.config.highlight_color);
+    f.render_stateful_widget(calendar, sidebar[0], &mut app.state_calendar);
 
     let selected_date = app.state_calendar.get_selected_date();
-    app.state_events.events = app.files.get_events_on_date(selected_date);
 
     let mut ev = EventViewWidget::new(
-        // app.files.get_events_on_date(app.state_calendar.get_selected_date()),
+        selected_date,
+        // app.store.get_events_on_date(app.state_calendar.get_selected_date()),
         app.state_events.events.clone(),
         app.enhanced_graphics,
     )
@@ -92,25 +173,54 @@ where
         selected_date.day(),
         Month::from_u32(selected_date.month()).unwrap().name(),
         selected_date.year(),
-        if let Some(i) = app.state_events.selected {
-            format!(
-                " - {}",
-                app.state_events.events.iter().nth(i).unwrap().desc()
-            )
-        } else {
-            String::new()
+        match app.state_events.selected_event() {
+            Some(event) => format!(" - {}", event.desc()),
+            None => String::new(),
         }
     )))
     .style(match app.input_mode.current() {
         Some(InputMode::Select) => Style::default().fg(Color::Yellow),
         _ => Style::default(),
     })
-    .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+    .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+    .config(&app.config);
     f.render_stateful_widget(ev, chunks[1], &mut app.state_events);
 
+    if app.state_events.show_details {
+        if let Some(event) = app.state_events.selected_event() {
+            let details_area = centered_rect(popup::HEIGHT / 2 + 2, popup::WIDTH, chunks[1]);
+            let details = EventDetailsWidget::new(event).block(
+                Block::default()
+                    .title("Details")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::White))
+                    .border_type(BorderType::Rounded)
+                    .style(Style::default().bg(Color::Black)),
+            );
+            f.render_widget(Clear, details_area);
+            f.render_widget(details, details_area);
+        }
+    }
+
+    if let (Some(event), Some(versions)) =
+        (app.state_events.selected_event(), &app.state_events.history)
+    {
+        let history_area = centered_rect(popup::HEIGHT / 2 + 2, popup::WIDTH, chunks[1]);
+        let history = EventHistoryWidget::new(event, versions).block(
+            Block::default()
+                .title("History")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::White))
+                .border_type(BorderType::Rounded)
+                .style(Style::default().bg(Color::Black)),
+        );
+        f.render_widget(Clear, history_area);
+        f.render_widget(history, history_area);
+    }
+
     let popup = PopupWidget::new().block(
         Block::default()
-            .title("Add event")
+            .title(app.state_popup.title())
             .borders(Borders::ALL)
             .border_style(Style::default().fg(Color::White))
             .border_type(BorderType::Rounded)
@@ -122,36 +232,96 @@ where
     } else {
         centered_rect(popup::HEIGHT, popup::WIDTH, chunks[1])
     };
-    if app.state_popup.visible {
+    if let Some(InputMode::Search) = app.input_mode.current() {
+        let search_area = centered_rect(popup::HEIGHT / 2 + 2, popup::WIDTH, chunks[1]);
+        let search = SearchViewWidget::new()
+            .block(
+                Block::default()
+                    .title(format!("Search - {} found", app.state_search.results.len()))
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow))
+                    .border_type(BorderType::Rounded)
+                    .style(Style::default().bg(Color::Black)),
+            )
+            .highlight_style(Style::default().bg(app.config.highlight_color));
+        f.render_widget(Clear, search_area);
+        f.render_stateful_widget(search, search_area, &mut app.state_search);
+    }
+
+    if app.state_popup.visible && app.state_popup.action == PopupAction::Goto {
+        draw_goto(f, &app.state_popup.goto, chunks[1]);
+    } else if app.state_popup.visible {
         f.render_widget(Clear, popup_area); //clear the background
         f.render_stateful_widget(popup, popup_area, &mut app.state_popup);
     }
 }
 
+/// Prompt for the id of the event to jump to
+fn draw_goto<B>(f: &mut Frame<B>, id: &str, area: Rect)
+where
+    B: Backend,
+{
+    let goto_area = centered_rect(3, popup::WIDTH / 2, area);
+    let paragraph = Paragraph::new(id).block(
+        Block::default()
+            .title("Go to event id")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .border_type(BorderType::Rounded)
+            .style(Style::default().bg(Color::Black)),
+    );
+    f.render_widget(Clear, goto_area);
+    f.render_widget(paragraph, goto_area);
+}
+
 fn draw_second_tab<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
 where
     B: Backend,
 {
-    let constraints = vec![Constraint::Percentage(100)];
-    let chunks = Layout::default()
-        .constraints(constraints)
-        .direction(Direction::Horizontal)
-        .split(area);
-    {
-        let chunks = Layout::default()
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
-            .split(chunks[0]);
-        {
-            let chunks = Layout::default()
-                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
-                .direction(Direction::Horizontal)
-                .split(chunks[0]);
-
-            // Draw logs
-            let info_style = Style::default().fg(Color::Blue);
-            let warning_style = Style::default().fg(Color::Yellow);
-            let error_style = Style::default().fg(Color::Magenta);
-            let critical_style = Style::default().fg(Color::Red);
-        }
+    let todos = TodoViewWidget::new()
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            "Todo - {} left",
+            app.state_todos
+                .todos
+                .iter()
+                .filter(|t| !t.is_completed())
+                .count()
+        )))
+        .highlight_style(Style::default().bg(app.config.highlight_color));
+    f.render_stateful_widget(todos, area, &mut app.state_todos);
+
+    let popup = TodoPopupWidget::new().block(
+        Block::default()
+            .title(app.state_popup.title())
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .border_type(BorderType::Rounded)
+            .style(Style::default().bg(Color::Black)),
+    );
+    let popup_area = centered_rect(popup::TODO_HEIGHT + 2, popup::WIDTH, area);
+    if app.state_popup.visible {
+        f.render_widget(Clear, popup_area); //clear the background
+        f.render_stateful_widget(popup, popup_area, &mut app.state_popup);
     }
 }
+
+fn draw_trash_tab<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let title = match app.config.trash_days {
+        0 => format!(
+            "Trash - {} events (r restore, p purge)",
+            app.state_trash.events.len()
+        ),
+        days => format!(
+            "Trash - {} events kept for {} days (r restore, p purge)",
+            app.state_trash.events.len(),
+            days
+        ),
+    };
+    let trash = TrashViewWidget::new()
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().bg(app.config.highlight_color));
+    f.render_stateful_widget(trash, area, &mut app.state_trash);
+}
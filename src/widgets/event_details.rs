@@ -0,0 +1,88 @@
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans, Text},
+    widgets::{Block, Paragraph, Widget, Wrap},
+};
+
+use crate::event::{format_reminders, format_tags, Event};
+
+/// Details of the selected event
+pub struct EventDetailsWidget<'a> {
+    event: &'a Event,
+    block: Option<Block<'a>>,
+}
+
+impl<'a> EventDetailsWidget<'a> {
+    pub fn new(event: &'a Event) -> Self {
+        EventDetailsWidget { event, block: None }
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> EventDetailsWidget<'a> {
+        self.block = Some(block);
+        self
+    }
+}
+
+/// Line with a highlighted name of the field
+fn field<'a>(name: &'a str, value: String) -> Spans<'a> {
+    Spans::from(vec![
+        Span::styled(
+            format!("{}: ", name),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(value),
+    ])
+}
+
+impl<'a> Widget for EventDetailsWidget<'a> {
+    fn render(mut self, area: Rect, buf: &mut Buffer) {
+        let event = self.event;
+        let time = if event.is_all_day() {
+            String::from("all day")
+        } else {
+            format!(
+                "{} - {}",
+                event.local_start().format("%Y-%m-%d %H:%M"),
+                event.local_end().format("%Y-%m-%d %H:%M")
+            )
+        };
+
+        let mut lines = vec![
+            field("Description", event.desc()),
+            field("Time", time),
+            field("Calendar", event.calendar().to_string()),
+        ];
+        if let Some(rrule) = event.rrule() {
+            lines.push(field("Repeat", rrule.to_string()));
+        }
+        if !event.tags().is_empty() {
+            lines.push(field("Tags", format_tags(event.tags())));
+        }
+        if !event.reminders().is_empty() {
+            lines.push(field(
+                "Remind",
+                format!("{} min before", format_reminders(event.reminders())),
+            ));
+        }
+        if let Some(location) = event.location() {
+            lines.push(field("Location", location.to_string()));
+        }
+        if let Some(url) = event.url() {
+            lines.push(field("URL", url.to_string()));
+        }
+        if let Some(notes) = event.notes() {
+            lines.push(field("Notes", String::new()));
+            lines.extend(notes.lines().map(|l| Spans::from(l.to_string())));
+        }
+
+        let mut paragraph = Paragraph::new(Text::from(lines)).wrap(Wrap { trim: false });
+        if let Some(block) = self.block.take() {
+            paragraph = paragraph.block(block);
+        }
+        paragraph.render(area, buf);
+    }
+}
@@ -0,0 +1,138 @@
+use chrono::NaiveDateTime;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans, Text},
+    widgets::{Block, Paragraph, Widget, Wrap},
+};
+
+use crate::event::{format_tags, Event};
+
+/// When the selected event was saved and its versions replaced by the updates
+pub struct EventHistoryWidget<'a> {
+    event: &'a Event,
+    versions: &'a [Event],
+    block: Option<Block<'a>>,
+}
+
+impl<'a> EventHistoryWidget<'a> {
+    /// `versions` are the replaced versions of the event, the latest first
+    pub fn new(event: &'a Event, versions: &'a [Event]) -> Self {
+        EventHistoryWidget {
+            event,
+            versions,
+            block: None,
+        }
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> EventHistoryWidget<'a> {
+        self.block = Some(block);
+        self
+    }
+}
+
+fn format_saved(time: Option<NaiveDateTime>, by: Option<&str>) -> String {
+    let time = time.map_or_else(
+        || String::from("unknown time"),
+        |t| t.format("%Y-%m-%d %H:%M").to_string(),
+    );
+    match by {
+        Some(by) => format!("{} by {}", time, by),
+        None => time,
+    }
+}
+
+fn format_time(event: &Event) -> String {
+    format!(
+        "{} - {}",
+        event.local_start().format("%Y-%m-%d %H:%M"),
+        event.local_end().format("%Y-%m-%d %H:%M")
+    )
+}
+
+/// Values of the `older` version of the event changed in the `newer` one
+fn changes(older: &Event, newer: &Event) -> Vec<String> {
+    let mut changes = Vec::new();
+    if older.start() != newer.start() || older.end() != newer.end() {
+        changes.push(format!("time was {}", format_time(older)));
+    }
+    if older.desc() != newer.desc() {
+        changes.push(format!("description was \"{}\"", older.desc()));
+    }
+    if older.rrule() != newer.rrule() {
+        let rrule = older
+            .rrule()
+            .map_or_else(|| String::from("none"), |r| r.to_string());
+        changes.push(format!("repeat was {}", rrule));
+    }
+    if older.calendar() != newer.calendar() {
+        changes.push(format!("calendar was {}", older.calendar()));
+    }
+    if older.tags() != newer.tags() {
+        changes.push(format!("tags were {}", format_tags(older.tags())));
+    }
+    if older.location() != newer.location() {
+        changes.push(format!(
+            "location was {}",
+            older.location().unwrap_or("none")
+        ));
+    }
+    if older.url() != newer.url() {
+        changes.push(format!("URL was {}", older.url().unwrap_or("none")));
+    }
+    if older.notes() != newer.notes() {
+        changes.push(String::from("notes changed"));
+    }
+    if older.deleted_at().is_some() && newer.deleted_at().is_none() {
+        changes.push(String::from("restored from the trash"));
+    }
+    changes
+}
+
+impl<'a> Widget for EventHistoryWidget<'a> {
+    fn render(mut self, area: Rect, buf: &mut Buffer) {
+        let heading = Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD);
+        let mut lines = vec![
+            Spans::from(vec![
+                Span::styled("Created: ", heading),
+                Span::raw(format_saved(self.event.created_at(), None)),
+            ]),
+            Spans::from(vec![
+                Span::styled("Updated: ", heading),
+                Span::raw(format_saved(
+                    self.event.updated_at(),
+                    self.event.updated_by(),
+                )),
+            ]),
+        ];
+
+        if self.versions.is_empty() {
+            lines.push(Spans::from("Never changed"));
+        }
+        // Each version is compared with the one which replaced it
+        let newer = std::iter::once(self.event).chain(self.versions.iter());
+        for (version, newer) in self.versions.iter().zip(newer) {
+            lines.push(Spans::from(Span::styled(
+                format!(
+                    "Changed {}",
+                    format_saved(newer.updated_at(), newer.updated_by())
+                ),
+                heading,
+            )));
+            lines.extend(
+                changes(version, newer)
+                    .into_iter()
+                    .map(|c| Spans::from(format!("  {}", c))),
+            );
+        }
+
+        let mut paragraph = Paragraph::new(Text::from(lines)).wrap(Wrap { trim: false });
+        if let Some(block) = self.block.take() {
+            paragraph = paragraph.block(block);
+        }
+        paragraph.render(area, buf);
+    }
+}
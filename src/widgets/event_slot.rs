@@ -1,6 +1,6 @@
 use std::ops::Mul;
 
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
 use chrono::{NaiveTime, Timelike};
 use std::ops::Bound::*;
 use std::ops::RangeBounds;
@@ -20,26 +20,52 @@ use tui::{
 
 use crate::event::{Event, EventTime};
 
+/// Text of a slot with the index of its event, events with
+/// the same description get slots of their own
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SlotLabel {
+    pub text: String,
+    pub index: usize,
+}
+
+impl std::fmt::Display for SlotLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
 pub struct EventSlot {
-    info: InnerInfo<NaiveDateTime, String>,
+    info: InnerInfo<NaiveDateTime, SlotLabel>,
+    date: NaiveDate,
     style: Style,
 }
 
 impl EventSlot {
-    pub fn new(info: InnerInfo<NaiveDateTime, String>, style: Style, debug: String) -> Self {
-        EventSlot { info, style }
+    pub fn new(
+        info: InnerInfo<NaiveDateTime, SlotLabel>,
+        date: NaiveDate,
+        style: Style,
+        debug: String,
+    ) -> Self {
+        EventSlot { info, date, style }
     }
 }
 
 impl Widget for EventSlot {
     fn render(mut self, area: Rect, buf: &mut Buffer) {
+        // Clip the event to the visible day
+        let day_start = self.date.and_hms_opt(0, 0, 0).unwrap();
+        let hours = |time: NaiveDateTime| -> f64 {
+            ((time - day_start).num_minutes() as f64 / 60f64).clamp(0.0, 24.0)
+        };
+
         let interval = self.info.interval();
-        let start: f64 = interval.start().hour() as f64 + interval.start().minute() as f64 / 60f64;
-        let end: f64 = interval.end().hour() as f64 + interval.end().minute() as f64 / 60f64;
+        let start: f64 = hours(interval.start());
+        let end: f64 = hours(interval.end());
 
         let duration = end - start;
 
-        let text = self.info.value();
+        let text = self.info.value().text;
 
         // slot area
         let r = Rect {
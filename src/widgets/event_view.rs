@@ -1,5 +1,5 @@
 use centered_interval_tree::CenteredIntervalTree;
-use chrono::{DateTime, Local, NaiveDateTime, NaiveTime};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime};
 
 use tui::{
     buffer::Buffer,
@@ -14,19 +14,34 @@ use tui::{
     },
 };
 
-use crate::event::Event;
+use crate::{event::Event, files::Config};
 
-use super::{event_slot::EventSlot, time_grid::TimeGrid};
+use super::{
+    event_slot::{EventSlot, SlotLabel},
+    time_grid::TimeGrid,
+};
 
 #[derive(Debug)]
 pub(crate) struct EventViewState {
     pub selected: Option<usize>,
     pub events: Vec<Event>,
+    pub show_details: bool,
+    /// Replaced versions of the selected event when its history is shown
+    pub history: Option<Vec<Event>>,
 }
 
 impl EventViewState {
     pub fn new(selected: Option<usize>, events: Vec<Event>) -> Self {
-        EventViewState { selected, events }
+        EventViewState {
+            selected,
+            events,
+            show_details: false,
+            history: None,
+        }
+    }
+
+    pub fn selected_event(&self) -> Option<&Event> {
+        self.selected.and_then(|i| self.events.get(i))
     }
 
     pub fn select(&mut self, index: Option<usize>) {
@@ -35,7 +50,12 @@ impl EventViewState {
 }
 
 pub(crate) struct EventViewWidget<'a> {
-    event_tree: CenteredIntervalTree<NaiveDateTime, String>,
+    date: NaiveDate,
+    all_day_events: Vec<Event>,
+    event_tree: CenteredIntervalTree<NaiveDateTime, SlotLabel>,
+    /// Timed events at the indexes of their slot labels
+    timed_events: Vec<Event>,
+    config: Option<&'a Config>,
     style: Style,
     block: Option<Block<'a>>,
     highlight_style: Style,
@@ -44,18 +64,31 @@ pub(crate) struct EventViewWidget<'a> {
 }
 
 impl<'a> EventViewWidget<'a> {
-    pub fn new(events: Vec<Event>, enhanced_graphics: bool) -> Self {
-        let mut tree = CenteredIntervalTree::<NaiveDateTime, String>::new(); //IntervalTree::<NaiveTime, String>::new();
+    /// Create the view of the events on `date`
+    pub fn new(date: NaiveDate, events: Vec<Event>, enhanced_graphics: bool) -> Self {
+        let mut tree = CenteredIntervalTree::<NaiveDateTime, SlotLabel>::new(); //IntervalTree::<NaiveTime, String>::new();
+        let (all_day_events, events): (Vec<Event>, Vec<Event>) =
+            events.into_iter().partition(|e| e.is_all_day());
 
-        for event in events.iter() {
+        for (index, event) in events.iter().enumerate() {
             tree.add(
-                centered_interval_tree::interval::Interval::new(event.start(), event.end()),
-                event.desc(),
+                centered_interval_tree::interval::Interval::new(
+                    event.local_start(),
+                    event.local_end(),
+                ),
+                SlotLabel {
+                    text: continuation_label(event, date),
+                    index,
+                },
             );
         }
 
         EventViewWidget {
+            date,
+            all_day_events,
             event_tree: tree,
+            timed_events: events,
+            config: None,
             block: None,
             style: Style::default(),
             highlight_symbol: None,
@@ -69,6 +102,20 @@ impl<'a> EventViewWidget<'a> {
         self
     }
 
+    /// Use the colors of the config for the tags and calendars of the events
+    pub fn config(mut self, config: &'a Config) -> EventViewWidget<'a> {
+        self.config = Some(config);
+        self
+    }
+
+    fn color(&self, event: Option<&Event>) -> Color {
+        match (self.config, event) {
+            (Some(config), Some(event)) => config.event_color(event),
+            (Some(config), None) => config.event_color,
+            (None, _) => Color::Blue,
+        }
+    }
+
     pub fn style(mut self, style: Style) -> EventViewWidget<'a> {
         self.style = style;
         self
@@ -85,6 +132,28 @@ impl<'a> EventViewWidget<'a> {
     }
 }
 
+/// Description of the event marked if it continues from
+/// the previous day or to the next day of the local time
+fn continuation_label(event: &Event, date: NaiveDate) -> String {
+    let day_start = date.and_hms_opt(0, 0, 0).unwrap();
+    let day_end = day_start + Duration::days(1);
+
+    format!(
+        "{}{}{}",
+        if event.local_start() < day_start {
+            "↑ "
+        } else {
+            ""
+        },
+        event.desc(),
+        if event.local_end() > day_end {
+            " ↓"
+        } else {
+            ""
+        }
+    )
+}
+
 impl<'a> StatefulWidget for EventViewWidget<'a> {
     type State = EventViewState;
 
@@ -103,6 +172,42 @@ impl<'a> StatefulWidget for EventViewWidget<'a> {
             return;
         }
 
+        // All-day events are listed in a strip above the time grid
+        let strip_height = (self.all_day_events.len() as u16).min(block_area.height / 4);
+        for (i, event) in self
+            .all_day_events
+            .iter()
+            .take(strip_height as usize)
+            .enumerate()
+        {
+            let style = if state.selected == Some(i) {
+                Style::default().bg(Color::Red).fg(Color::White)
+            } else {
+                Style::default()
+                    .bg(self.color(Some(event)))
+                    .fg(Color::White)
+            };
+            let line = Rect {
+                y: block_area.y + i as u16,
+                height: 1,
+                ..block_area
+            };
+            buf.set_style(line, style);
+            buf.set_stringn(
+                line.x + 1,
+                line.y,
+                continuation_label(event, self.date),
+                line.width.saturating_sub(1) as usize,
+                style,
+            );
+        }
+        let block_area = Rect {
+            y: block_area.y + strip_height,
+            height: block_area.height - strip_height,
+            ..block_area
+        };
+        let offset = self.all_day_events.len();
+
         let chunks = Layout::default().direction(Direction::Horizontal);
         let max_slots = self.event_tree.overlaps() + 1;
         let mut constraints = vec![];
@@ -116,13 +221,13 @@ impl<'a> StatefulWidget for EventViewWidget<'a> {
         tg.render(block_area, buf);
 
         for (i, (info, mut layer, has_overlaps)) in self.event_tree.iter().enumerate() {
-            let style = if state.selected.is_some() && state.selected.unwrap() == i {
+            let style = if state.selected == Some(i + offset) {
                 Style::default().fg(Color::Red)
             } else {
-                Style::default().fg(Color::Blue)
+                Style::default().fg(self.color(self.timed_events.get(info.value().index)))
             };
 
-            let slot = EventSlot::new(info, style, layer.to_string());
+            let slot = EventSlot::new(info, self.date, style, layer.to_string());
 
             let mut chunks_union = chunks[layer];
             while !has_overlaps && layer != max_slots {
@@ -1,7 +1,12 @@
 pub mod calendar;
+pub mod event_details;
+pub mod event_history;
 pub mod event_slot;
 pub mod event_view;
 pub mod grid;
 pub mod popup;
+pub mod search_view;
 pub mod time_grid;
+pub mod todo_view;
+pub mod trash_view;
 pub mod weeks;
@@ -2,20 +2,24 @@ use std::ops::Div;
 
 use tui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
-    widgets::{Block, Borders, Paragraph, StatefulWidget, Widget},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph, StatefulWidget, Widget, Wrap},
 };
 
 use crate::{
     app::InputMode,
+    event::EditScope,
     popup::{
         input::{PopupInput, PopupInputState},
         state::PopupState,
+        todo::TodoInputState,
     },
 };
 
 pub const WIDTH: u16 = 60;
-pub const HEIGHT: u16 = 16;
+pub const HEIGHT: u16 = 22;
+pub const TODO_HEIGHT: u16 = 6;
 
 pub struct PopupWidget<'a> {
     pub block: Option<Block<'a>>,
@@ -42,9 +46,9 @@ pub fn centered_rect(height: u16, width: u16, r: Rect) -> Rect {
         .direction(Direction::Vertical)
         .constraints(
             [
-                Constraint::Length(r.height / 2 - height / 2),
+                Constraint::Length((r.height / 2).saturating_sub(height / 2)),
                 Constraint::Length(height),
-                Constraint::Length(r.height / 2 - height / 2),
+                Constraint::Length((r.height / 2).saturating_sub(height / 2)),
             ]
             .as_ref(),
         )
@@ -54,9 +58,9 @@ pub fn centered_rect(height: u16, width: u16, r: Rect) -> Rect {
         .direction(Direction::Horizontal)
         .constraints(
             [
-                Constraint::Length(r.width / 2 - width / 2),
+                Constraint::Length((r.width / 2).saturating_sub(width / 2)),
                 Constraint::Length(width),
-                Constraint::Length(r.width / 2 - width / 2),
+                Constraint::Length((r.width / 2).saturating_sub(width / 2)),
             ]
             .as_ref(),
         )
@@ -77,18 +81,47 @@ impl<'a> StatefulWidget for PopupWidget<'a> {
             None => area,
         };
 
+        // Reserve the first row for the scope of the changes to a recurring event,
+        // the notes take the rest of the space
+        let rows = if state.input.scope.is_some() { 6 } else { 5 };
+        let mut constraints = vec![Constraint::Length(3); rows];
+        constraints.push(Constraint::Min(3));
         let layout = Layout::default()
-            .constraints(
-                [
-                    Constraint::Percentage(33),
-                    Constraint::Percentage(33),
-                    Constraint::Percentage(33),
-                ]
-                .as_ref(),
-            )
+            .constraints(constraints)
             .direction(Direction::Vertical)
             .split(block_area);
 
+        let layout = if let Some(scope) = state.input.scope {
+            let scopes = [EditScope::This, EditScope::Following, EditScope::All];
+            let spans: Vec<Span> = scopes
+                .iter()
+                .map(|s| {
+                    if *s == scope {
+                        Span::styled(
+                            format!(" {} ", s),
+                            Style::default().add_modifier(Modifier::REVERSED),
+                        )
+                    } else {
+                        Span::raw(format!(" {} ", s))
+                    }
+                })
+                .collect();
+            let scope_par = Paragraph::new(Spans::from(spans))
+                .style(match state.input.state {
+                    PopupInputState::Scope => Style::default().fg(Color::Yellow),
+                    _ => Style::default(),
+                })
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Apply to (←/→)"),
+                );
+            scope_par.render(layout[0], buf);
+            layout[1..].to_vec()
+        } else {
+            layout
+        };
+
         let start_layout = Layout::default()
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
             .direction(Direction::Horizontal)
@@ -119,7 +152,7 @@ impl<'a> StatefulWidget for PopupWidget<'a> {
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title("Start time (hh:mm)"),
+                    .title("Start time (empty for all day)"),
             );
         start_time_par.render(start_layout[1], buf);
 
@@ -143,16 +176,186 @@ impl<'a> StatefulWidget for PopupWidget<'a> {
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title("End time (hh:mm)"),
+                    .title("End time (empty for all day)"),
             );
         end_time_par.render(end_layout[1], buf);
 
+        let repeat_layout = Layout::default()
+            .constraints(
+                [
+                    Constraint::Percentage(45),
+                    Constraint::Percentage(30),
+                    Constraint::Percentage(25),
+                ]
+                .as_ref(),
+            )
+            .direction(Direction::Horizontal)
+            .split(layout[2]);
+
+        let recurrence_par = Paragraph::new(state.input.recurrence.as_ref())
+            .style(match state.input.state {
+                PopupInputState::Recurrence => Style::default().fg(Color::Yellow),
+                _ => Style::default(),
+            })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Repeat (weekly or RRULE)"),
+            );
+        recurrence_par.render(repeat_layout[0], buf);
+        let timezone_par = Paragraph::new(state.input.timezone.as_ref())
+            .style(match state.input.state {
+                PopupInputState::TimeZone => Style::default().fg(Color::Yellow),
+                _ => Style::default(),
+            })
+            .block(Block::default().borders(Borders::ALL).title("Time zone"));
+        timezone_par.render(repeat_layout[1], buf);
+        let reminders_par = Paragraph::new(state.input.reminders.as_ref())
+            .style(match state.input.state {
+                PopupInputState::Reminders => Style::default().fg(Color::Yellow),
+                _ => Style::default(),
+            })
+            .block(Block::default().borders(Borders::ALL).title("Remind (min)"));
+        reminders_par.render(repeat_layout[2], buf);
+
+        let description_layout = Layout::default()
+            .constraints(
+                [
+                    Constraint::Percentage(45),
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(35),
+                ]
+                .as_ref(),
+            )
+            .direction(Direction::Horizontal)
+            .split(layout[3]);
+
         let description_par = Paragraph::new(state.input.description.as_ref())
             .style(match state.input.state {
                 PopupInputState::Description => Style::default().fg(Color::Yellow),
                 _ => Style::default(),
             })
             .block(Block::default().borders(Borders::ALL).title("Description"));
-        description_par.render(layout[2], buf);
+        description_par.render(description_layout[0], buf);
+        let calendar_par = Paragraph::new(state.input.calendar.as_ref())
+            .style(match state.input.state {
+                PopupInputState::Calendar => Style::default().fg(Color::Yellow),
+                _ => Style::default(),
+            })
+            .block(Block::default().borders(Borders::ALL).title("Calendar"));
+        calendar_par.render(description_layout[1], buf);
+        let tags_par = Paragraph::new(state.input.tags.as_ref())
+            .style(match state.input.state {
+                PopupInputState::Tags => Style::default().fg(Color::Yellow),
+                _ => Style::default(),
+            })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Tags (comma separated)"),
+            );
+        tags_par.render(description_layout[2], buf);
+
+        let location_layout = Layout::default()
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .direction(Direction::Horizontal)
+            .split(layout[4]);
+
+        let location_par = Paragraph::new(state.input.location.as_ref())
+            .style(match state.input.state {
+                PopupInputState::Location => Style::default().fg(Color::Yellow),
+                _ => Style::default(),
+            })
+            .block(Block::default().borders(Borders::ALL).title("Location"));
+        location_par.render(location_layout[0], buf);
+        let url_par = Paragraph::new(state.input.url.as_ref())
+            .style(match state.input.state {
+                PopupInputState::Url => Style::default().fg(Color::Yellow),
+                _ => Style::default(),
+            })
+            .block(Block::default().borders(Borders::ALL).title("URL"));
+        url_par.render(location_layout[1], buf);
+
+        let notes_par = Paragraph::new(state.input.notes.as_ref())
+            .style(match state.input.state {
+                PopupInputState::Notes => Style::default().fg(Color::Yellow),
+                _ => Style::default(),
+            })
+            .wrap(Wrap { trim: false })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Notes (Enter for a new line, Tab to save)"),
+            );
+        notes_par.render(layout[5], buf);
+    }
+}
+
+/// Popup with the form of a todo
+pub struct TodoPopupWidget<'a> {
+    pub block: Option<Block<'a>>,
+}
+
+impl<'a> TodoPopupWidget<'a> {
+    pub fn new() -> Self {
+        TodoPopupWidget { block: None }
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> TodoPopupWidget<'a> {
+        self.block = Some(block);
+        self
+    }
+}
+
+impl<'a> StatefulWidget for TodoPopupWidget<'a> {
+    type State = PopupState;
+
+    fn render(mut self, area: Rect, buf: &mut tui::buffer::Buffer, state: &mut Self::State) {
+        let block_area = match self.block.take() {
+            Some(b) => {
+                let inner_area = b.inner(area);
+                b.render(area, buf);
+                inner_area
+            }
+            None => area,
+        };
+
+        let layout = Layout::default()
+            .constraints([Constraint::Length(3), Constraint::Length(3)].as_ref())
+            .direction(Direction::Vertical)
+            .split(block_area);
+        let bottom_layout = Layout::default()
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+            .direction(Direction::Horizontal)
+            .split(layout[1]);
+
+        let field_style = |field: TodoInputState| {
+            if state.todo.state == field {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            }
+        };
+
+        Paragraph::new(state.todo.description.as_ref())
+            .style(field_style(TodoInputState::Description))
+            .block(Block::default().borders(Borders::ALL).title("Description"))
+            .render(layout[0], buf);
+        Paragraph::new(state.todo.priority.as_ref())
+            .style(field_style(TodoInputState::Priority))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Priority (1-9)"),
+            )
+            .render(bottom_layout[0], buf);
+        Paragraph::new(state.todo.due.as_ref())
+            .style(field_style(TodoInputState::Due))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Due date (YYYY-MM-DD)"),
+            )
+            .render(bottom_layout[1], buf);
     }
 }
@@ -0,0 +1,109 @@
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, StatefulWidget, Widget},
+};
+
+use crate::event::Event;
+
+#[derive(Debug, Default)]
+pub(crate) struct SearchViewState {
+    pub query: String,
+    pub selected: Option<usize>,
+    pub results: Vec<Event>,
+}
+
+impl SearchViewState {
+    pub fn select(&mut self, index: Option<usize>) {
+        self.selected = index;
+    }
+
+    pub fn selected_event(&self) -> Option<&Event> {
+        self.selected.and_then(|i| self.results.get(i))
+    }
+}
+
+/// Query being typed and the events matching it with their dates
+pub(crate) struct SearchViewWidget<'a> {
+    block: Option<Block<'a>>,
+    highlight_style: Style,
+}
+
+impl<'a> SearchViewWidget<'a> {
+    pub fn new() -> Self {
+        SearchViewWidget {
+            block: None,
+            highlight_style: Style::default(),
+        }
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> SearchViewWidget<'a> {
+        self.block = Some(block);
+        self
+    }
+
+    pub fn highlight_style(mut self, style: Style) -> SearchViewWidget<'a> {
+        self.highlight_style = style;
+        self
+    }
+}
+
+impl<'a> StatefulWidget for SearchViewWidget<'a> {
+    type State = SearchViewState;
+
+    fn render(mut self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let block_area = match self.block.take() {
+            Some(b) => {
+                let inner_area = b.inner(area);
+                b.render(area, buf);
+                inner_area
+            }
+            None => area,
+        };
+
+        if block_area.width < 1 || block_area.height < 2 {
+            return;
+        }
+
+        let prompt = Spans::from(vec![
+            Span::styled("/", Style::default().fg(Color::Yellow)),
+            Span::raw(state.query.as_str()),
+        ]);
+        buf.set_spans(block_area.x, block_area.y, &prompt, block_area.width);
+
+        // Keep the selected result visible below the prompt
+        let height = block_area.height as usize - 1;
+        let offset = state.selected.map_or(0, |i| (i + 1).saturating_sub(height));
+
+        for (i, event) in state.results.iter().enumerate().skip(offset).take(height) {
+            let style = if state.selected == Some(i) {
+                self.highlight_style
+            } else {
+                Style::default()
+            };
+            let date = if event.is_all_day() {
+                event.local_start().format("%Y-%m-%d      ").to_string()
+            } else {
+                event.local_start().format("%Y-%m-%d %H:%M").to_string()
+            };
+            let spans = Spans::from(vec![
+                Span::styled(
+                    format!("{} ", date),
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(event.desc(), style),
+                Span::styled(
+                    if event.is_recurring() { " ↻" } else { "" },
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]);
+
+            let y = block_area.y + 1 + (i - offset) as u16;
+            buf.set_spans(block_area.x, y, &spans, block_area.width);
+        }
+    }
+}
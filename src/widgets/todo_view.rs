@@ -0,0 +1,126 @@
+use chrono::{Local, NaiveDate};
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, StatefulWidget, Widget},
+};
+
+use crate::todo::Todo;
+
+#[derive(Debug)]
+pub(crate) struct TodoViewState {
+    pub selected: Option<usize>,
+    pub todos: Vec<Todo>,
+}
+
+impl TodoViewState {
+    pub fn new(selected: Option<usize>, todos: Vec<Todo>) -> Self {
+        TodoViewState { selected, todos }
+    }
+
+    pub fn select(&mut self, index: Option<usize>) {
+        self.selected = index;
+    }
+
+    pub fn selected_todo(&self) -> Option<&Todo> {
+        self.selected.and_then(|i| self.todos.get(i))
+    }
+}
+
+pub(crate) struct TodoViewWidget<'a> {
+    today: NaiveDate,
+    style: Style,
+    block: Option<Block<'a>>,
+    highlight_style: Style,
+}
+
+impl<'a> TodoViewWidget<'a> {
+    pub fn new() -> Self {
+        TodoViewWidget {
+            today: Local::now().date_naive(),
+            style: Style::default(),
+            block: None,
+            highlight_style: Style::default(),
+        }
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> TodoViewWidget<'a> {
+        self.block = Some(block);
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> TodoViewWidget<'a> {
+        self.style = style;
+        self
+    }
+
+    pub fn highlight_style(mut self, style: Style) -> TodoViewWidget<'a> {
+        self.highlight_style = style;
+        self
+    }
+}
+
+impl<'a> StatefulWidget for TodoViewWidget<'a> {
+    type State = TodoViewState;
+
+    fn render(mut self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        buf.set_style(area, self.style);
+        let block_area = match self.block.take() {
+            Some(b) => {
+                let inner_area = b.inner(area);
+                b.render(area, buf);
+                inner_area
+            }
+            None => area,
+        };
+
+        if block_area.width < 1 || block_area.height < 1 {
+            return;
+        }
+
+        // Keep the selected todo visible
+        let height = block_area.height as usize;
+        let offset = state.selected.map_or(0, |i| (i + 1).saturating_sub(height));
+
+        for (i, todo) in state.todos.iter().enumerate().skip(offset).take(height) {
+            let mut style = if todo.is_completed() {
+                Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::CROSSED_OUT)
+            } else {
+                Style::default()
+            };
+            if state.selected == Some(i) {
+                style = style.patch(self.highlight_style);
+            }
+
+            let due_style = if todo.is_overdue(self.today) {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default().fg(Color::Cyan)
+            };
+            let spans = Spans::from(vec![
+                Span::styled(
+                    format!(
+                        "[{}] {} ",
+                        if todo.is_completed() { "x" } else { " " },
+                        todo.priority()
+                    ),
+                    style,
+                ),
+                Span::styled(todo.desc(), style),
+                Span::styled(
+                    todo.due()
+                        .map(|d| format!(" (due {})", d))
+                        .unwrap_or_default(),
+                    due_style,
+                ),
+            ]);
+
+            let y = block_area.y + (i - offset) as u16;
+            buf.set_spans(block_area.x, y, &spans, block_area.width);
+        }
+    }
+}
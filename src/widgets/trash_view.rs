@@ -0,0 +1,100 @@
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Span, Spans},
+    widgets::{Block, StatefulWidget, Widget},
+};
+
+use crate::event::Event;
+
+#[derive(Debug)]
+pub(crate) struct TrashViewState {
+    pub selected: Option<usize>,
+    pub events: Vec<Event>,
+}
+
+impl TrashViewState {
+    pub fn new(selected: Option<usize>, events: Vec<Event>) -> Self {
+        TrashViewState { selected, events }
+    }
+
+    pub fn select(&mut self, index: Option<usize>) {
+        self.selected = index;
+    }
+
+    pub fn selected_event(&self) -> Option<&Event> {
+        self.selected.and_then(|i| self.events.get(i))
+    }
+}
+
+/// Deleted events with the dates they took place and were deleted on
+pub(crate) struct TrashViewWidget<'a> {
+    block: Option<Block<'a>>,
+    highlight_style: Style,
+}
+
+impl<'a> TrashViewWidget<'a> {
+    pub fn new() -> Self {
+        TrashViewWidget {
+            block: None,
+            highlight_style: Style::default(),
+        }
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> TrashViewWidget<'a> {
+        self.block = Some(block);
+        self
+    }
+
+    pub fn highlight_style(mut self, style: Style) -> TrashViewWidget<'a> {
+        self.highlight_style = style;
+        self
+    }
+}
+
+impl<'a> StatefulWidget for TrashViewWidget<'a> {
+    type State = TrashViewState;
+
+    fn render(mut self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let block_area = match self.block.take() {
+            Some(b) => {
+                let inner_area = b.inner(area);
+                b.render(area, buf);
+                inner_area
+            }
+            None => area,
+        };
+
+        if block_area.width < 1 || block_area.height < 1 {
+            return;
+        }
+
+        // Keep the selected event visible
+        let height = block_area.height as usize;
+        let offset = state.selected.map_or(0, |i| (i + 1).saturating_sub(height));
+
+        for (i, event) in state.events.iter().enumerate().skip(offset).take(height) {
+            let style = if state.selected == Some(i) {
+                self.highlight_style
+            } else {
+                Style::default()
+            };
+            let deleted_at = event
+                .deleted_at()
+                .map(|t| t.format("deleted %Y-%m-%d %H:%M ").to_string())
+                .unwrap_or_default();
+            let spans = Spans::from(vec![
+                Span::styled(deleted_at, Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    event.local_start().format("%Y-%m-%d %H:%M ").to_string(),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::styled(event.desc(), style),
+            ]);
+
+            let y = block_area.y + (i - offset) as u16;
+            buf.set_spans(block_area.x, y, &spans, block_area.width);
+        }
+    }
+}
@@ -4,7 +4,7 @@ use chrono::{Datelike, Duration, Local, Month, NaiveDate, Weekday};
 use num_traits::FromPrimitive;
 use rayday::get_days_from_month;
 use tui::{
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::{Span, Spans, Text},
 };
 
@@ -36,7 +36,15 @@ impl<'a> Weeks<'a> {
         start.checked_sub_signed(Duration::days(a)).unwrap()
     }
 
-    pub fn new(today: NaiveDate /*Date<Local>*/, height: u16, width: u16) -> Self {
+    /// Build the weeks content, underlining the days from `event_dates`
+    /// and coloring the days from `all_day_dates`
+    pub fn new(
+        today: NaiveDate, /*Date<Local>*/
+        height: u16,
+        width: u16,
+        event_dates: &[NaiveDate],
+        all_day_dates: &[NaiveDate],
+    ) -> Self {
         let mut curr_date = Self::get_curr_date(today, height, width);
         let mut curr_month = curr_date.month();
         let mut curr_height = 0; // first month name
@@ -81,10 +89,18 @@ impl<'a> Weeks<'a> {
             while (curr_date.weekday() != Weekday::Mon || spans.len() == 0)
                 && curr_month == curr_date.month()
             {
+                let fg = if all_day_dates.contains(&curr_date) {
+                    Color::LightMagenta
+                } else {
+                    Color::White
+                };
                 if curr_date == today {
-                    highlight_style = Style::default().fg(Color::White).bg(Color::Blue);
+                    highlight_style = Style::default().fg(fg).bg(Color::Blue);
                 } else {
-                    highlight_style = Style::default().fg(Color::White);
+                    highlight_style = Style::default().fg(fg);
+                }
+                if event_dates.contains(&curr_date) {
+                    highlight_style = highlight_style.add_modifier(Modifier::UNDERLINED);
                 }
 
                 if curr_date.day0() == 0 {